@@ -0,0 +1,10 @@
+-- Conversation history for iterative refinement of a draft
+CREATE TABLE refinement_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id INTEGER NOT NULL REFERENCES kb_articles(id),
+    role TEXT NOT NULL,                      -- 'user' | 'assistant'
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_refinement_messages_article ON refinement_messages(article_id);
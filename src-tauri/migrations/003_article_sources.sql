@@ -0,0 +1,5 @@
+-- Articles drafted from several related tickets record all of them.
+-- ticket_key keeps the primary (first) ticket.
+ALTER TABLE kb_articles ADD COLUMN source_ticket_keys TEXT NOT NULL DEFAULT '[]';  -- JSON array
+
+UPDATE kb_articles SET source_ticket_keys = json_array(ticket_key) WHERE ticket_key IS NOT NULL;
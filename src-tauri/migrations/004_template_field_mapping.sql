@@ -0,0 +1,15 @@
+-- Which NewArticle field each output_structure heading fills, as a JSON
+-- object of heading -> field name. Headings not listed are matched by name.
+ALTER TABLE kb_templates ADD COLUMN field_mapping TEXT NOT NULL DEFAULT '{}';
+
+UPDATE kb_templates SET field_mapping = '{"Problem":"problem","Environment":"prerequisites","Resolution":"solution","Expected Result":"expected_result","Additional Notes":"additional_notes"}'
+WHERE id = 'tpl-troubleshoot';
+
+UPDATE kb_templates SET field_mapping = '{"Overview":"problem","Prerequisites":"prerequisites","Steps":"solution","Verification":"expected_result","Troubleshooting":"additional_notes"}'
+WHERE id = 'tpl-howto';
+
+UPDATE kb_templates SET field_mapping = '{"Symptoms":"problem","Affected Systems":"prerequisites","Workaround":"solution","Status":"additional_notes","Related Tickets":"additional_notes"}'
+WHERE id = 'tpl-known-issue';
+
+UPDATE kb_templates SET field_mapping = '{"Answer":"solution","Details":"additional_notes","See Also":"additional_notes"}'
+WHERE id = 'tpl-faq';
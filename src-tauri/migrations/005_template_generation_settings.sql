@@ -0,0 +1,14 @@
+-- Sampling settings per template, as a JSON GenerationSettings object, and
+-- an optional model used instead of the one configured in the settings
+ALTER TABLE kb_templates ADD COLUMN generation_settings TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE kb_templates ADD COLUMN model TEXT;
+
+-- FAQ entries should be short and come out the same every time
+UPDATE kb_templates SET generation_settings = '{"temperature":0.2,"top_p":0.8,"seed":42,"num_predict":1024}'
+WHERE id = 'tpl-faq';
+
+UPDATE kb_templates SET generation_settings = '{"temperature":0.3}'
+WHERE id IN ('tpl-troubleshoot', 'tpl-known-issue');
+
+UPDATE kb_templates SET generation_settings = '{"temperature":0.7,"top_p":0.9}'
+WHERE id = 'tpl-howto';
@@ -0,0 +1,21 @@
+-- Inputs and raw output of every draft generation. article_id is set once
+-- the draft is saved.
+CREATE TABLE draft_provenance (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id INTEGER REFERENCES kb_articles(id),
+    ticket_keys TEXT NOT NULL DEFAULT '[]',  -- JSON array
+    template_id TEXT,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    options TEXT NOT NULL DEFAULT '{}',      -- JSON GenerationSettings
+    system_prompt TEXT NOT NULL,
+    user_prompt TEXT NOT NULL,
+    raw_output TEXT NOT NULL,
+    structured INTEGER NOT NULL DEFAULT 0,
+    prompt_eval_count INTEGER,
+    eval_count INTEGER,
+    duration_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_draft_provenance_article ON draft_provenance(article_id);
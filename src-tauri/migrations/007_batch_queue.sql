@@ -0,0 +1,30 @@
+-- Background drafting of every ticket matched by a JQL search
+CREATE TABLE batch_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    jql TEXT NOT NULL,
+    template_id TEXT NOT NULL REFERENCES kb_templates(id),
+    jira_url TEXT NOT NULL,
+    provider TEXT NOT NULL DEFAULT 'ollama',  -- 'ollama' | 'openai_compatible'
+    llm_url TEXT NOT NULL,
+    model TEXT NOT NULL,
+    context_length INTEGER,
+    concurrency INTEGER NOT NULL DEFAULT 2,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+    status TEXT NOT NULL DEFAULT 'running',   -- 'running' | 'finished' | 'cancelled'
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE batch_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    batch_id INTEGER NOT NULL REFERENCES batch_runs(id),
+    ticket_key TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued',    -- 'queued' | 'running' | 'done' | 'failed' | 'cancelled'
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    article_id INTEGER REFERENCES kb_articles(id),
+    next_attempt_at TEXT,                     -- retry not before this time
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (batch_id, ticket_key)
+);
+
+CREATE INDEX idx_batch_jobs_batch ON batch_jobs(batch_id, status);
@@ -0,0 +1,11 @@
+-- Embedding of every article, used to spot duplicates before drafting.
+-- content_hash identifies the text that was embedded, so stale vectors are
+-- recomputed after an edit.
+CREATE TABLE article_embeddings (
+    article_id INTEGER PRIMARY KEY REFERENCES kb_articles(id),
+    model TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,                    -- little-endian f32 values
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
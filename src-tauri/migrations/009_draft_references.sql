@@ -0,0 +1,2 @@
+-- Existing articles retrieved into the drafting prompt as reference material
+ALTER TABLE draft_provenance ADD COLUMN article_references TEXT NOT NULL DEFAULT '[]';  -- JSON array of ArticleReference
@@ -0,0 +1,3 @@
+-- Sensitive values replaced by placeholders before ticket content was sent
+-- to the model, so they can be restored in the draft on request
+ALTER TABLE draft_provenance ADD COLUMN redactions TEXT NOT NULL DEFAULT '[]';  -- JSON array of Redaction
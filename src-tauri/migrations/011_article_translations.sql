@@ -0,0 +1,6 @@
+-- Articles can be written in other languages. A translation is a sibling
+-- article linked to the one it was translated from.
+ALTER TABLE kb_articles ADD COLUMN language TEXT NOT NULL DEFAULT 'en';  -- ISO 639-1 code
+ALTER TABLE kb_articles ADD COLUMN translation_of INTEGER REFERENCES kb_articles(id);
+
+CREATE INDEX idx_kb_articles_translation_of ON kb_articles(translation_of);
@@ -0,0 +1,3 @@
+-- Problems found in the source tickets while drafting, such as text that
+-- tries to give the model instructions, kept for review after saving
+ALTER TABLE draft_provenance ADD COLUMN warnings TEXT NOT NULL DEFAULT '[]';  -- JSON array of strings
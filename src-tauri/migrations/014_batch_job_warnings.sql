@@ -0,0 +1,4 @@
+-- What drafting found out about a batch ticket, shown next to the job since
+-- nobody watches the draft come in
+ALTER TABLE batch_jobs ADD COLUMN warnings TEXT NOT NULL DEFAULT '[]';    -- JSON array of strings
+ALTER TABLE batch_jobs ADD COLUMN ungrounded TEXT NOT NULL DEFAULT '[]';  -- JSON array of FlaggedSection
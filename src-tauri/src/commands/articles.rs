@@ -1,19 +1,235 @@
-use crate::db::{articles, DbPool};
+use crate::db::{articles, embeddings, provenance, templates, DbPool};
 use crate::error::AppError;
-use crate::models::{Article, NewArticle, QualityScore};
-use crate::services::{quality, sensitive_data};
+use crate::models::{
+    Article, ConformanceReport, DuplicateMatch, JiraTicket, Language, NewArticle, QualityScore,
+    TagSuggestion, Template,
+};
+use crate::services::{
+    article_fields, conformance, duplicates, grounding, ollama, quality, sensitive_data,
+    tag_suggester, translator,
+};
+use rusqlite::Connection;
+use std::collections::HashMap;
 use tauri::State;
 
+/// Save a new draft. With `ollama_url` set, its embedding for duplicate
+/// detection is refreshed too (see `find_duplicate_articles`).
 #[tauri::command]
-pub async fn save_draft(article: NewArticle, db: State<'_, DbPool>) -> Result<Article, AppError> {
+pub async fn save_draft(
+    mut article: NewArticle,
+    ollama_url: Option<String>,
+    embedding_model: Option<String>,
+    db: State<'_, DbPool>,
+) -> Result<Article, AppError> {
     let pool = db.inner().clone();
-    tokio::task::spawn_blocking(move || -> Result<Article, AppError> {
+    let saved = tokio::task::spawn_blocking(move || -> Result<Article, AppError> {
         let conn = pool.get()?;
+        sync_fields(&conn, &mut article)?;
         let id = articles::insert_article(&conn, &article)?;
+        if let Some(provenance_id) = article.provenance_id {
+            provenance::link_article(&conn, provenance_id, id)?;
+        }
         Ok(articles::get_article(&conn, id)?)
     })
     .await
-    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    refresh_embedding(db.inner(), &saved, ollama_url, embedding_model).await;
+    Ok(saved)
+}
+
+/// Update a draft, refreshing its embedding like `save_draft`
+#[tauri::command]
+pub async fn update_draft(
+    id: i64,
+    mut article: NewArticle,
+    ollama_url: Option<String>,
+    embedding_model: Option<String>,
+    db: State<'_, DbPool>,
+) -> Result<Article, AppError> {
+    let pool = db.inner().clone();
+    let saved = tokio::task::spawn_blocking(move || -> Result<Article, AppError> {
+        let conn = pool.get()?;
+        article.translation_of = articles::get_article(&conn, id)?.translation_of;
+        sync_fields(&conn, &mut article)?;
+        articles::update_article(&conn, id, &article)?;
+        if let Some(provenance_id) = article.provenance_id {
+            provenance::link_article(&conn, provenance_id, id)?;
+        }
+        Ok(articles::get_article(&conn, id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    refresh_embedding(db.inner(), &saved, ollama_url, embedding_model).await;
+    Ok(saved)
+}
+
+/// Re-embed a saved article. Failing is not an error: the article is saved,
+/// and `find_duplicate_articles` embeds anything stale before comparing.
+pub(crate) async fn refresh_embedding(
+    pool: &DbPool,
+    article: &Article,
+    ollama_url: Option<String>,
+    embedding_model: Option<String>,
+) {
+    let Some(ollama_url) = ollama_url else {
+        return;
+    };
+    let model = embedding_model.unwrap_or_else(|| duplicates::DEFAULT_EMBEDDING_MODEL.to_string());
+    if let Err(e) = embed_articles(pool, &ollama_url, &model, &[article]).await {
+        log::warn!("Failed to embed article {}: {}", article.id, e);
+    }
+}
+
+/// Embed `to_embed` in batches and store the vectors, returning them by article id
+async fn embed_articles(
+    pool: &DbPool,
+    ollama_url: &str,
+    model: &str,
+    to_embed: &[&Article],
+) -> Result<Vec<(i64, Vec<f32>)>, AppError> {
+    let mut embedded = Vec::new();
+
+    for chunk in to_embed.chunks(duplicates::EMBED_BATCH_SIZE) {
+        let texts: Vec<String> = chunk.iter().map(|a| duplicates::article_text(a)).collect();
+        let vectors = ollama::embed(ollama_url, model, &texts).await?;
+
+        let rows: Vec<(i64, String, Vec<f32>)> = chunk
+            .iter()
+            .zip(&texts)
+            .zip(vectors)
+            .map(|((article, text), vector)| (article.id, duplicates::content_hash(text), vector))
+            .collect();
+
+        let pool = pool.clone();
+        let model = model.to_string();
+        let rows = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+            let conn = pool.get()?;
+            for (article_id, hash, vector) in &rows {
+                embeddings::upsert_embedding(&conn, *article_id, &model, hash, vector)?;
+            }
+            Ok(rows)
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+
+        embedded.extend(rows.into_iter().map(|(id, _, vector)| (id, vector)));
+    }
+
+    Ok(embedded)
+}
+
+/// Existing articles most similar to `tickets`, so the user can update one
+/// of them instead of drafting a duplicate. Meant to run before `draft_with_llm`.
+///
+/// Embeddings come from Ollama's `/api/embed` with `embedding_model`
+/// (`nomic-embed-text` when omitted). Articles without an up-to-date
+/// embedding for that model are embedded first. The tickets are redacted
+/// before they are embedded, as they are before drafting.
+#[tauri::command]
+pub async fn find_duplicate_articles(
+    tickets: Vec<JiraTicket>,
+    ollama_url: String,
+    embedding_model: Option<String>,
+    limit: Option<usize>,
+    db: State<'_, DbPool>,
+) -> Result<Vec<DuplicateMatch>, AppError> {
+    let (tickets, _) = sensitive_data::redact_tickets(&tickets);
+    let query_text = duplicates::ticket_text(&tickets);
+    if query_text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let model = embedding_model.unwrap_or_else(|| duplicates::DEFAULT_EMBEDDING_MODEL.to_string());
+
+    let pool = db.inner().clone();
+    let stored_model = model.clone();
+    let (all_articles, stored) = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+        let conn = pool.get()?;
+        Ok((
+            articles::list_articles(&conn, None)?,
+            embeddings::list_embeddings(&conn, &stored_model)?,
+        ))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    let mut vectors: HashMap<i64, Vec<f32>> = HashMap::new();
+    let mut stale: Vec<&Article> = Vec::new();
+    // Translations would only repeat their original
+    for article in all_articles.iter().filter(|a| a.translation_of.is_none()) {
+        let hash = duplicates::content_hash(&duplicates::article_text(article));
+        match stored.iter().find(|e| e.article_id == article.id) {
+            Some(embedding) if embedding.content_hash == hash => {
+                vectors.insert(article.id, embedding.vector.clone());
+            }
+            _ => stale.push(article),
+        }
+    }
+    if !stale.is_empty() {
+        log::info!("Embedding {} articles with {}", stale.len(), model);
+    }
+    vectors.extend(embed_articles(db.inner(), &ollama_url, &model, &stale).await?);
+
+    let query = ollama::embed(&ollama_url, &model, &[query_text])
+        .await?
+        .pop()
+        .unwrap_or_default();
+    let candidates: Vec<(i64, Vec<f32>)> = vectors.into_iter().collect();
+    let ranked = duplicates::rank(
+        &query,
+        &candidates,
+        duplicates::MIN_SIMILARITY,
+        limit.unwrap_or(duplicates::DEFAULT_MATCH_LIMIT),
+    );
+
+    Ok(ranked
+        .into_iter()
+        .filter_map(|(id, similarity)| {
+            let article = all_articles.iter().find(|a| a.id == id)?;
+            Some(DuplicateMatch {
+                article_id: article.id,
+                title: article.title.clone(),
+                ticket_key: article.ticket_key.clone(),
+                status: article.status.clone(),
+                confluence_url: article.confluence_url.clone(),
+                similarity,
+            })
+        })
+        .collect())
+}
+
+/// Refresh the article fields from `content_markdown` using the template's
+/// section mapping, so hand edits to the markdown reach the columns.
+/// Translations are matched to the sections of the article they were
+/// translated from, since their headings are not the template's.
+pub(crate) fn sync_fields(conn: &Connection, article: &mut NewArticle) -> Result<(), AppError> {
+    let template = article_template(conn, article.template_id.as_deref())?;
+    if let Some(source_id) = article.translation_of {
+        let source = articles::get_article(conn, source_id)?;
+        let empty = Default::default();
+        let mapping = template.as_ref().map(|t| &t.field_mapping).unwrap_or(&empty);
+        if translator::sync_fields(article, &source.content_markdown, mapping) {
+            return Ok(());
+        }
+    }
+    article_fields::sync_fields(article, template.as_ref());
+    Ok(())
+}
+
+/// The template an article was drafted with, if it still exists
+fn article_template(
+    conn: &Connection,
+    template_id: Option<&str>,
+) -> Result<Option<Template>, AppError> {
+    match template_id {
+        Some(template_id) => match templates::get_template(conn, template_id) {
+            Ok(template) => Ok(Some(template)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        },
+        None => Ok(None),
+    }
 }
 
 #[tauri::command]
@@ -41,6 +257,27 @@ pub async fn list_articles(
     .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
+/// Every language version of an article, the original first
+#[tauri::command]
+pub async fn list_article_translations(
+    article_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<Vec<Article>, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<Article>, AppError> {
+        let conn = pool.get()?;
+        Ok(articles::list_translations(&conn, article_id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Languages articles can be drafted or translated into
+#[tauri::command]
+pub async fn list_languages() -> Result<Vec<Language>, AppError> {
+    Ok(translator::languages())
+}
+
 #[tauri::command]
 pub async fn delete_draft(id: i64, db: State<'_, DbPool>) -> Result<(), AppError> {
     let pool = db.inner().clone();
@@ -73,12 +310,93 @@ pub async fn export_markdown(
     Ok(())
 }
 
+/// Score an article. Its sections are checked against its template too,
+/// with mismatches reported as warnings.
 #[tauri::command]
-pub async fn score_quality(article: NewArticle) -> Result<QualityScore, AppError> {
-    Ok(quality::score(&article))
+pub async fn score_quality(
+    article: NewArticle,
+    db: State<'_, DbPool>,
+) -> Result<QualityScore, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<QualityScore, AppError> {
+        let conn = pool.get()?;
+        let template = article_template(&conn, article.template_id.as_deref())?;
+        Ok(quality::score(&article, template.as_ref()))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Compare the sections of `content` with the template's output structure:
+/// missing, extra, renamed and out-of-order sections
+#[tauri::command]
+pub async fn check_template_conformance(
+    content: String,
+    template_id: String,
+    db: State<'_, DbPool>,
+) -> Result<ConformanceReport, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<ConformanceReport, AppError> {
+        let conn = pool.get()?;
+        let template = templates::get_template(&conn, &template_id)?;
+        Ok(conformance::check(&content, &template))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Add the template sections `content` is missing, with placeholder bodies.
+/// Returns the updated markdown; nothing is saved.
+#[tauri::command]
+pub async fn insert_missing_sections(
+    content: String,
+    template_id: String,
+    db: State<'_, DbPool>,
+) -> Result<String, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<String, AppError> {
+        let conn = pool.get()?;
+        let template = templates::get_template(&conn, &template_id)?;
+        Ok(conformance::insert_missing(&content, &template))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
 #[tauri::command]
 pub async fn scan_sensitive_data(content: String) -> Result<Vec<crate::models::FlaggedSection>, AppError> {
     Ok(sensitive_data::scan(&content))
 }
+
+/// Re-run the grounding check after the user edits a draft
+#[tauri::command]
+pub async fn check_grounding(
+    content: String,
+    tickets: Vec<JiraTicket>,
+) -> Result<Vec<crate::models::FlaggedSection>, AppError> {
+    Ok(grounding::check(&content, &tickets))
+}
+
+/// Suggest Confluence labels for an article from its source tickets, its
+/// content and the tags already used in the knowledge base
+#[tauri::command]
+pub async fn suggest_tags(
+    content_markdown: String,
+    tickets: Option<Vec<JiraTicket>>,
+    limit: Option<usize>,
+    db: State<'_, DbPool>,
+) -> Result<Vec<TagSuggestion>, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<TagSuggestion>, AppError> {
+        let conn = pool.get()?;
+        let existing = articles::tag_counts(&conn)?;
+        Ok(tag_suggester::suggest(
+            &tickets.unwrap_or_default(),
+            &content_markdown,
+            &existing,
+            limit.unwrap_or(tag_suggester::DEFAULT_SUGGESTION_LIMIT),
+        ))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
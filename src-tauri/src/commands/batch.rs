@@ -0,0 +1,473 @@
+use super::articles::{refresh_embedding, sync_fields};
+use super::drafting::{llm_provider, reference_articles};
+use super::jira::JiraSettings;
+use crate::db::{articles, batches, provenance, templates, DbPool};
+use crate::error::AppError;
+use crate::models::{BatchJob, BatchRun, FlaggedSection, LlmProviderKind, NewBatchRun};
+use crate::services::{batch_queue, drafter, jira::JiraClient, tokens};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Event emitted with a `BatchJob` whenever a job changes status
+pub const BATCH_JOB_EVENT: &str = "batch-job-status";
+
+/// Event emitted with a `BatchRun` when a batch finishes or is cancelled
+pub const BATCH_STATUS_EVENT: &str = "batch-status";
+
+/// How often an idle worker checks for jobs whose retry has come due
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A ticket a batch job drafted and saved
+struct Drafted {
+    article_id: i64,
+    warnings: Vec<String>,
+    ungrounded: Vec<FlaggedSection>,
+}
+
+/// Batches currently being worked on, keyed by batch id, so they can be cancelled
+#[derive(Default)]
+pub struct ActiveBatches {
+    handles: HashMap<i64, tokio::task::AbortHandle>,
+}
+
+/// Queue every ticket matching `jql` for drafting with `template_id` and
+/// start working through them in the background. Each draft is saved as an
+/// article; progress is reported through `batch-job-status` events. Queries
+/// matching more than `MAX_BATCH_TICKETS` tickets are rejected.
+///
+/// `concurrency` (default 2) limits how many tickets are drafted at once and
+/// `max_attempts` (default 3) how often a ticket is tried before it is
+/// marked failed. The LLM parameters work like those of `draft_article`.
+/// Unfinished batches resume when the app is started again.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_batch_draft(
+    jql: String,
+    template_id: String,
+    ollama_url: String,
+    model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    concurrency: Option<usize>,
+    max_attempts: Option<u32>,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    settings: State<'_, Mutex<JiraSettings>>,
+) -> Result<BatchRun, AppError> {
+    if jql.trim().is_empty() {
+        return Err(AppError::Internal("JQL query is empty".to_string()));
+    }
+
+    let jira_url = {
+        let settings = settings
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock settings: {}", e)))?;
+        settings
+            .base_url
+            .clone()
+            .ok_or_else(|| AppError::Internal("Jira not configured".to_string()))?
+    };
+
+    let credentials = tokens::get_credentials("jira")?;
+    let (keys, total) = JiraClient::new(jira_url.clone(), credentials)
+        .search_keys(&jql, batch_queue::MAX_BATCH_TICKETS)
+        .await?;
+    if keys.is_empty() {
+        return Err(AppError::Internal("No tickets match the query".to_string()));
+    }
+    // Better than a batch that reports finished with tickets left out
+    if total > batch_queue::MAX_BATCH_TICKETS {
+        return Err(AppError::Internal(format!(
+            "The query matches {} tickets, but a batch drafts at most {}. Narrow it down, \
+             e.g. by date range, and start one batch per part.",
+            total,
+            batch_queue::MAX_BATCH_TICKETS
+        )));
+    }
+    log::info!(
+        "Queueing {} tickets for batch drafting: {}",
+        keys.len(),
+        jql
+    );
+
+    let new_batch = NewBatchRun {
+        jql,
+        template_id,
+        jira_url,
+        provider: provider.unwrap_or_default(),
+        llm_url: ollama_url,
+        model,
+        context_length,
+        concurrency: concurrency
+            .unwrap_or(batch_queue::DEFAULT_CONCURRENCY)
+            .clamp(1, batch_queue::MAX_CONCURRENCY),
+        max_attempts: max_attempts
+            .unwrap_or(batch_queue::DEFAULT_MAX_ATTEMPTS)
+            .max(1),
+    };
+    let batch = with_conn(db.inner(), move |conn| {
+        // Fail now rather than once per ticket
+        templates::get_template(conn, &new_batch.template_id)?;
+        let id = batches::create_batch(conn, &new_batch, &keys)?;
+        Ok(batches::get_batch(conn, id)?)
+    })
+    .await?;
+
+    spawn_runner(&app, batch.clone());
+    Ok(batch)
+}
+
+/// List all batches, newest first, with how many of their jobs are done
+#[tauri::command]
+pub async fn list_batches(db: State<'_, DbPool>) -> Result<Vec<BatchRun>, AppError> {
+    with_conn(db.inner(), |conn| Ok(batches::list_batches(conn)?)).await
+}
+
+/// List the jobs of a batch with their status, attempts and saved article
+#[tauri::command]
+pub async fn get_batch_jobs(
+    batch_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<Vec<BatchJob>, AppError> {
+    with_conn(db.inner(), move |conn| {
+        Ok(batches::list_jobs(conn, batch_id)?)
+    })
+    .await
+}
+
+/// Stop a batch. Tickets already drafted stay saved; the rest are cancelled.
+#[tauri::command]
+pub async fn cancel_batch(
+    batch_id: i64,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    active: State<'_, Mutex<ActiveBatches>>,
+) -> Result<BatchRun, AppError> {
+    {
+        let mut active = active
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock batches: {}", e)))?;
+        if let Some(handle) = active.handles.remove(&batch_id) {
+            handle.abort();
+        }
+    }
+
+    let batch = with_conn(db.inner(), move |conn| {
+        batches::cancel_batch(conn, batch_id)?;
+        Ok(batches::get_batch(conn, batch_id)?)
+    })
+    .await?;
+
+    emit_batch(&app, &batch);
+    Ok(batch)
+}
+
+/// Queue the failed and cancelled tickets of a batch again
+#[tauri::command]
+pub async fn retry_batch_failures(
+    batch_id: i64,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+) -> Result<BatchRun, AppError> {
+    let batch = with_conn(db.inner(), move |conn| {
+        let requeued = batches::retry_failed_jobs(conn, batch_id)?;
+        log::info!("Retrying {} jobs of batch {}", requeued, batch_id);
+        Ok(batches::get_batch(conn, batch_id)?)
+    })
+    .await?;
+
+    spawn_runner(&app, batch.clone());
+    Ok(batch)
+}
+
+/// Pick up the batches that were still running when the app was closed.
+/// Called once at startup.
+pub fn resume_batches(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<DbPool>().inner().clone();
+        let running = with_conn(&pool, |conn| {
+            let requeued = batches::requeue_interrupted_jobs(conn)?;
+            if requeued > 0 {
+                log::info!("Requeued {} interrupted batch jobs", requeued);
+            }
+            Ok(batches::list_running_batches(conn)?)
+        })
+        .await;
+
+        match running {
+            Ok(running) => {
+                for batch in running {
+                    log::info!("Resuming batch {}: {}", batch.id, batch.jql);
+                    spawn_runner(&app, batch);
+                }
+            }
+            Err(e) => log::error!("Failed to resume batches: {}", e),
+        }
+    });
+}
+
+/// Start working on a batch unless it is already being worked on
+fn spawn_runner(app: &AppHandle, batch: BatchRun) {
+    let active = app.state::<Mutex<ActiveBatches>>();
+    let mut active = match active.lock() {
+        Ok(active) => active,
+        Err(e) => {
+            log::error!("Failed to lock batches: {}", e);
+            return;
+        }
+    };
+
+    let running = active
+        .handles
+        .get(&batch.id)
+        .is_some_and(|handle| !handle.is_finished());
+    if running {
+        return;
+    }
+
+    let batch_id = batch.id;
+    let task = tokio::spawn(run_batch(app.clone(), batch));
+    active.handles.insert(batch_id, task.abort_handle());
+}
+
+/// Work through a batch with `concurrency` workers until no job is left
+/// queued or running, then mark it finished
+async fn run_batch(app: AppHandle, batch: BatchRun) {
+    let pool = app.state::<DbPool>().inner().clone();
+    let batch_id = batch.id;
+    let batch = Arc::new(batch);
+
+    let finished = loop {
+        let mut workers = tokio::task::JoinSet::new();
+        for _ in 0..batch.concurrency.clamp(1, batch_queue::MAX_CONCURRENCY) {
+            workers.spawn(work(app.clone(), pool.clone(), batch.clone()));
+        }
+        while workers.join_next().await.is_some() {}
+
+        // Jobs requeued by `retry_batch_failures` while the last workers
+        // were winding down get a new round instead of being left behind.
+        // Deciding and unregistering under the `ActiveBatches` lock means a
+        // retry either finds this runner still registered and leaves the
+        // jobs to it, or finds it gone and starts a new one.
+        let finalize_app = app.clone();
+        let finalize_pool = pool.clone();
+        let finished = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+            let active = finalize_app.state::<Mutex<ActiveBatches>>();
+            let mut active = active
+                .lock()
+                .map_err(|e| AppError::Internal(format!("Failed to lock batches: {}", e)))?;
+            let conn = finalize_pool.get()?;
+            if !batches::finish_batch(&conn, batch_id)? {
+                return Ok(None);
+            }
+            active.handles.remove(&batch_id);
+            Ok(Some(batches::get_batch(&conn, batch_id)?))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))
+        .and_then(|result| result);
+
+        match finished {
+            Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+            Ok(Some(batch)) => break Ok(batch),
+            Err(e) => break Err(e),
+        }
+    };
+
+    match finished {
+        Ok(batch) => {
+            log::info!(
+                "Batch {} finished: {} drafted, {} failed",
+                batch.id,
+                batch.completed_jobs,
+                batch.failed_jobs
+            );
+            emit_batch(&app, &batch);
+        }
+        Err(e) => {
+            log::error!("Failed to finish batch {}: {}", batch_id, e);
+            if let Ok(mut active) = app.state::<Mutex<ActiveBatches>>().lock() {
+                active.handles.remove(&batch_id);
+            }
+        }
+    }
+}
+
+/// One worker: keep claiming due jobs, waiting for retries to come due,
+/// until the batch has nothing left to do
+async fn work(app: AppHandle, pool: DbPool, batch: Arc<BatchRun>) {
+    let batch_id = batch.id;
+    loop {
+        let claimed = with_conn(&pool, move |conn| {
+            Ok(batches::claim_next_job(conn, batch_id)?)
+        })
+        .await;
+        match claimed {
+            Ok(Some(job)) => {
+                emit_job(&app, &job);
+                run_job(&app, &pool, &batch, job).await;
+            }
+            Ok(None) => {
+                let unfinished = with_conn(&pool, move |conn| {
+                    Ok(batches::has_unfinished_jobs(conn, batch_id)?)
+                })
+                .await;
+                match unfinished {
+                    Ok(true) => tokio::time::sleep(POLL_INTERVAL).await,
+                    Ok(false) => break,
+                    Err(e) => {
+                        log::error!("Batch {} worker stopped: {}", batch_id, e);
+                        break;
+                    }
+                }
+            }
+            Err(e) => {
+                log::error!("Batch {} worker stopped: {}", batch_id, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Draft one ticket and record the outcome, scheduling a retry if it failed
+/// for a reason that may go away
+async fn run_job(app: &AppHandle, pool: &DbPool, batch: &BatchRun, job: BatchJob) {
+    let job_id = job.id;
+    let outcome = draft_ticket(pool, batch, &job.ticket_key).await;
+
+    let recorded = match outcome {
+        Ok(drafted) => {
+            log::info!("Batch {} drafted {}", batch.id, job.ticket_key);
+            with_conn(pool, move |conn| {
+                Ok(batches::complete_job(
+                    conn,
+                    job_id,
+                    drafted.article_id,
+                    &drafted.warnings,
+                    &drafted.ungrounded,
+                )?)
+            })
+            .await
+        }
+        Err(e) => {
+            let retry_in = batch_queue::retry_delay(&e, job.attempts, batch.max_attempts);
+            log::warn!(
+                "Batch {} failed to draft {} (attempt {}): {}",
+                batch.id,
+                job.ticket_key,
+                job.attempts,
+                e
+            );
+            let message = e.to_string();
+            with_conn(pool, move |conn| {
+                Ok(batches::fail_job(conn, job_id, &message, retry_in)?)
+            })
+            .await
+        }
+    };
+    if let Err(e) = recorded {
+        log::error!("Failed to record batch job {}: {}", job_id, e);
+        return;
+    }
+
+    match with_conn(pool, move |conn| Ok(batches::get_job(conn, job_id)?)).await {
+        Ok(job) => emit_job(app, &job),
+        Err(e) => log::warn!("Failed to load batch job {}: {}", job_id, e),
+    }
+}
+
+/// Fetch, draft and save one ticket. The article is saved the way
+/// `save_draft` saves it; with Ollama, its embedding for duplicate detection
+/// is refreshed too.
+async fn draft_ticket(pool: &DbPool, batch: &BatchRun, key: &str) -> Result<Drafted, AppError> {
+    let template_id = batch.template_id.clone();
+    let template = with_conn(pool, move |conn| {
+        Ok(templates::get_template(conn, &template_id)?)
+    })
+    .await?;
+
+    let credentials = tokens::get_credentials("jira")?;
+    let ticket = JiraClient::new(batch.jira_url.clone(), credentials)
+        .get_ticket(key)
+        .await?;
+
+    let retrieval_ticket = ticket.clone();
+    let references = with_conn(pool, move |conn| {
+        reference_articles(conn, std::slice::from_ref(&retrieval_ticket), None)
+    })
+    .await?;
+
+    let model = template
+        .model
+        .clone()
+        .unwrap_or_else(|| batch.model.clone());
+    let provider = llm_provider(
+        Some(batch.provider),
+        &batch.llm_url,
+        &model,
+        batch.context_length,
+        &template.generation,
+    );
+    let (draft, record) = drafter::draft(
+        &[ticket],
+        &template,
+        &references,
+        provider.as_ref(),
+        &mut |_| {},
+    )
+    .await?;
+
+    let warnings = draft.warnings.clone();
+    let ungrounded = draft.ungrounded.clone();
+    let key = key.to_string();
+    let saved = with_conn(pool, move |conn| {
+        let provenance_id = provenance::insert_provenance(conn, &record)?;
+        let mut article = batch_queue::new_article(draft, &key, &template.id, Some(provenance_id));
+        sync_fields(conn, &mut article)?;
+        let article_id = articles::insert_article(conn, &article)?;
+        provenance::link_article(conn, provenance_id, article_id)?;
+        Ok(articles::get_article(conn, article_id)?)
+    })
+    .await?;
+
+    // Embeddings come from Ollama, so other backends leave them to
+    // `find_duplicate_articles`
+    let ollama_url = (batch.provider == LlmProviderKind::Ollama).then(|| batch.llm_url.clone());
+    refresh_embedding(pool, &saved, ollama_url, None).await;
+
+    Ok(Drafted {
+        article_id: saved.id,
+        warnings,
+        ungrounded,
+    })
+}
+
+/// Run `f` with a pooled connection on the blocking thread pool
+async fn with_conn<T, F>(pool: &DbPool, f: F) -> Result<T, AppError>
+where
+    F: FnOnce(&Connection) -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = pool.get()?;
+        f(&conn)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))?
+}
+
+fn emit_job(app: &AppHandle, job: &BatchJob) {
+    if let Err(e) = app.emit(BATCH_JOB_EVENT, job.clone()) {
+        log::warn!("Failed to emit batch job status: {}", e);
+    }
+}
+
+fn emit_batch(app: &AppHandle, batch: &BatchRun) {
+    if let Err(e) = app.emit(BATCH_STATUS_EVENT, batch.clone()) {
+        log::warn!("Failed to emit batch status: {}", e);
+    }
+}
@@ -1,27 +1,43 @@
 use crate::db::DbPool;
 use crate::error::AppError;
 use crate::models::confluence::{ConfluenceSpace, PublishResult};
-use crate::services::{confluence::ConfluenceClient, markdown_to_confluence, tokens};
+use crate::models::{AuthMode, Credentials};
+use crate::services::{
+    atlassian, confluence::ConfluenceClient, markdown_to_confluence, tokens, translator,
+};
 use tauri::State;
 
-/// Test Confluence connection
+/// Test Confluence connection and return the auth mode it accepted. Cloud
+/// needs `email` with an API token as `pat`; without `auth_mode` the
+/// deployment type is detected.
 #[tauri::command]
 pub async fn test_confluence_connection(
     base_url: String,
     pat: String,
-) -> Result<bool, AppError> {
-    let client = ConfluenceClient::new(base_url, pat);
-    client.test_connection().await
+    email: Option<String>,
+    auth_mode: Option<AuthMode>,
+) -> Result<AuthMode, AppError> {
+    let auth_mode = auth_mode.unwrap_or_default();
+    let credentials = detect_credentials(&base_url, &pat, email.as_deref(), auth_mode).await?;
+    Ok(credentials.mode())
 }
 
 /// Save Confluence configuration
 #[tauri::command]
 pub async fn save_confluence_config(
-    _base_url: String,
+    base_url: String,
     pat: String,
+    email: Option<String>,
+    auth_mode: Option<AuthMode>,
 ) -> Result<(), AppError> {
-    // Store PAT in keychain
-    tokens::store_token("confluence", &pat)?;
+    // Store credentials in keychain, probing Confluence first if the mode is not known
+    let credentials = match auth_mode.unwrap_or_default() {
+        AuthMode::Auto => {
+            detect_credentials(&base_url, &pat, email.as_deref(), AuthMode::Auto).await?
+        }
+        mode => atlassian::candidates(&base_url, mode, email.as_deref(), &pat)?.remove(0),
+    };
+    tokens::store_credentials("confluence", &credentials)?;
 
     // Store base URL in settings (we could use a settings table, but for now just return success)
     // The frontend will persist the base URL in localStorage
@@ -29,6 +45,21 @@ pub async fn save_confluence_config(
     Ok(())
 }
 
+/// The first of the candidate credentials Confluence accepts
+async fn detect_credentials(
+    base_url: &str,
+    token: &str,
+    email: Option<&str>,
+    auth_mode: AuthMode,
+) -> Result<Credentials, AppError> {
+    let candidates = atlassian::candidates(base_url, auth_mode, email, token)?;
+    atlassian::detect("confluence", candidates, |credentials| {
+        let client = ConfluenceClient::new(base_url.to_string(), credentials);
+        async move { client.test_connection().await }
+    })
+    .await
+}
+
 /// Disconnect from Confluence
 #[tauri::command]
 pub async fn disconnect_confluence() -> Result<(), AppError> {
@@ -49,8 +80,8 @@ pub async fn get_confluence_connection_status() -> Result<bool, AppError> {
 /// List available Confluence spaces
 #[tauri::command]
 pub async fn list_confluence_spaces(confluence_url: String) -> Result<Vec<ConfluenceSpace>, AppError> {
-    let pat = tokens::get_token("confluence")?;
-    let client = ConfluenceClient::new(confluence_url, pat);
+    let credentials = tokens::get_credentials("confluence")?;
+    let client = ConfluenceClient::new(confluence_url, credentials);
     client.list_spaces().await
 }
 
@@ -62,7 +93,7 @@ pub async fn publish_article(
     confluence_url: String,
     db: State<'_, DbPool>,
 ) -> Result<PublishResult, AppError> {
-    let pat = tokens::get_token("confluence")?;
+    let credentials = tokens::get_credentials("confluence")?;
 
     // Get article from database
     let pool = db.inner().clone();
@@ -76,10 +107,12 @@ pub async fn publish_article(
     // Convert markdown to Confluence XHTML
     let conversion_result = markdown_to_confluence::convert(&article.content_markdown)?;
 
-    // Create page in Confluence
-    let client = ConfluenceClient::new(confluence_url, pat);
+    // Create page in Confluence; every translation is a page of its own
+    let title = translator::page_title(&article.title, &article.language);
+    let labels = translator::page_labels(&article.tags, &article.language);
+    let client = ConfluenceClient::new(confluence_url, credentials);
     let publish_result = client
-        .create_page(&space_key, &article.title, &conversion_result.xhtml, &article.tags)
+        .create_page(&space_key, &title, &conversion_result.xhtml, &labels)
         .await?;
 
     // Update article in database with publish info
@@ -109,7 +142,7 @@ pub async fn update_published_article(
     confluence_url: String,
     db: State<'_, DbPool>,
 ) -> Result<PublishResult, AppError> {
-    let pat = tokens::get_token("confluence")?;
+    let credentials = tokens::get_credentials("confluence")?;
 
     // Get article from database
     let pool = db.inner().clone();
@@ -129,10 +162,11 @@ pub async fn update_published_article(
     let conversion_result = markdown_to_confluence::convert(&article.content_markdown)?;
 
     // Fetch current page version, then update
-    let client = ConfluenceClient::new(confluence_url, pat);
+    let client = ConfluenceClient::new(confluence_url, credentials);
     let current_version = client.get_page_version(&page_id).await?;
+    let title = translator::page_title(&article.title, &article.language);
     let publish_result = client
-        .update_page(&page_id, &article.title, &conversion_result.xhtml, current_version)
+        .update_page(&page_id, &title, &conversion_result.xhtml, current_version)
         .await?;
 
     // Update article URL in database
@@ -1,35 +1,798 @@
-use crate::db::DbPool;
+use crate::db::{articles, provenance, refinements, templates, DbPool};
 use crate::error::AppError;
 use crate::models::jira::JiraTicket;
-use crate::services::{drafter, ollama};
-use tauri::State;
+use crate::models::{
+    Article, ArticleDraft, ArticleReference, DraftProgress, DraftProvenance, GenerationSettings,
+    LlmProviderKind, NewArticle, NewDraftProvenance, OllamaModel, OllamaModelDetails,
+    RefinementMessage, DEFAULT_LANGUAGE,
+};
+use crate::services::llm::{self, ChatMessage, LlmProvider};
+use crate::services::{
+    drafter, grounding, ollama, related_articles, sensitive_data, tokens, translator,
+};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, State};
 
-/// Check if Ollama is available at the configured URL
+/// Event emitted for every chunk of streamed LLM output
+pub const DRAFT_PROGRESS_EVENT: &str = "draft-progress";
+
+/// Event emitted for every status line while a model is being pulled
+pub const MODEL_PULL_PROGRESS_EVENT: &str = "model-pull-progress";
+
+/// Drafts currently being generated, keyed by draft id, so they can be cancelled
+#[derive(Default)]
+pub struct ActiveDrafts {
+    handles: HashMap<String, tokio::task::AbortHandle>,
+}
+
+/// Build the configured LLM provider. OpenAI-compatible servers may require
+/// an API key, which is kept in the keychain next to the Jira and Confluence PATs.
+///
+/// `generation` holds the sampling settings of the template being drafted
+/// with, if any.
+pub(crate) fn llm_provider(
+    kind: Option<LlmProviderKind>,
+    url: &str,
+    model: &str,
+    context_length: Option<usize>,
+    generation: &GenerationSettings,
+) -> Box<dyn LlmProvider> {
+    let kind = kind.unwrap_or_default();
+    let api_key = match kind {
+        LlmProviderKind::Ollama => None,
+        LlmProviderKind::OpenaiCompatible => tokens::get_token("llm").ok(),
+    };
+    llm::provider_for(kind, url, model, api_key, context_length, generation)
+}
+
+/// The `count` existing articles most relevant to `tickets`, to draft with
+/// as reference material
+pub(crate) fn reference_articles(
+    conn: &Connection,
+    tickets: &[JiraTicket],
+    count: Option<usize>,
+) -> Result<Vec<ArticleReference>, AppError> {
+    let count = count.unwrap_or(related_articles::DEFAULT_REFERENCE_COUNT);
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    // Translations would only repeat their original, in another language
+    let originals: Vec<_> = articles::list_articles(conn, None)?
+        .into_iter()
+        .filter(|a| a.translation_of.is_none())
+        .collect();
+    Ok(related_articles::retrieve(tickets, &originals, count))
+}
+
+/// Check if the LLM server is available at the configured URL
+#[tauri::command]
+pub async fn check_ollama_status(
+    ollama_url: String,
+    provider: Option<LlmProviderKind>,
+) -> Result<bool, AppError> {
+    llm_provider(
+        provider,
+        &ollama_url,
+        "",
+        None,
+        &GenerationSettings::default(),
+    )
+    .check_health()
+    .await
+}
+
+/// List the models installed on the Ollama server
+#[tauri::command]
+pub async fn list_ollama_models(ollama_url: String) -> Result<Vec<OllamaModel>, AppError> {
+    ollama::list_models(&ollama_url).await
+}
+
+/// Show parameter size, quantization and context length of an Ollama model
+#[tauri::command]
+pub async fn show_ollama_model(
+    ollama_url: String,
+    model: String,
+) -> Result<OllamaModelDetails, AppError> {
+    ollama::show_model(&ollama_url, &model).await
+}
+
+/// Download a model through Ollama, emitting `model-pull-progress` events
+/// until it is installed
 #[tauri::command]
-pub async fn check_ollama_status(ollama_url: String) -> Result<bool, AppError> {
-    ollama::check_health(&ollama_url).await
+pub async fn pull_ollama_model(
+    ollama_url: String,
+    model: String,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    log::info!("Pulling Ollama model {}", model);
+    ollama::pull_model(&ollama_url, &model, &mut |progress| {
+        if let Err(e) = app.emit(MODEL_PULL_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit model pull progress: {}", e);
+        }
+    })
+    .await
 }
 
-/// Draft an article from a Jira ticket using LLM
+/// Store the API key for an OpenAI-compatible server. An empty key removes it.
 #[tauri::command]
+pub async fn save_llm_api_key(api_key: String) -> Result<(), AppError> {
+    if api_key.is_empty() {
+        // Nothing stored is fine too
+        let _ = tokens::delete_token("llm");
+        Ok(())
+    } else {
+        tokens::store_token("llm", &api_key)
+    }
+}
+
+/// Forward streamed output to the UI as `draft-progress` events
+fn progress_emitter(app: AppHandle, draft_id: String) -> impl FnMut(&str) + Send {
+    let mut chars_generated = 0;
+    move |delta| {
+        chars_generated += delta.chars().count();
+        let progress = DraftProgress {
+            draft_id: draft_id.clone(),
+            delta: delta.to_string(),
+            chars_generated,
+        };
+        if let Err(e) = app.emit(DRAFT_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit draft progress: {}", e);
+        }
+    }
+}
+
+/// Run a generation in its own task, registered under `draft_id` so
+/// `cancel_draft` can abort it
+async fn run_cancellable<F, T>(
+    drafts: &Mutex<ActiveDrafts>,
+    draft_id: &str,
+    generation: F,
+) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let task = {
+        let mut drafts = drafts
+            .lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock drafts: {}", e)))?;
+        if drafts.handles.contains_key(draft_id) {
+            return Err(AppError::Internal(format!(
+                "A draft for {} is already in progress",
+                draft_id
+            )));
+        }
+
+        let task = tokio::spawn(generation);
+        drafts
+            .handles
+            .insert(draft_id.to_string(), task.abort_handle());
+        task
+    };
+
+    let task_id = task.id();
+    let result = task.await;
+
+    // Once this task is done, `cancel_draft` may have removed its handle and
+    // a new draft may have been registered under the same id
+    if let Ok(mut drafts) = drafts.lock() {
+        if drafts
+            .handles
+            .get(draft_id)
+            .is_some_and(|handle| handle.id() == task_id)
+        {
+            drafts.handles.remove(draft_id);
+        }
+    }
+
+    match result {
+        Ok(output) => output,
+        Err(e) if e.is_cancelled() => Err(AppError::Cancelled),
+        Err(e) => Err(AppError::Internal(format!("Task join error: {}", e))),
+    }
+}
+
+/// Draft an article from a Jira ticket using LLM, returning its markdown.
+/// See `draft_article` for the parameters.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn draft_with_llm(
+    ticket: JiraTicket,
+    related_tickets: Option<Vec<JiraTicket>>,
+    template_id: String,
+    ollama_url: String,
+    model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    draft_id: Option<String>,
+    reference_count: Option<usize>,
+    language: Option<String>,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
+) -> Result<String, AppError> {
+    let draft = draft_article(
+        ticket,
+        related_tickets,
+        template_id,
+        ollama_url,
+        model,
+        provider,
+        context_length,
+        draft_id,
+        reference_count,
+        language,
+        app,
+        db,
+        drafts,
+    )
+    .await?;
+    Ok(draft.content_markdown)
+}
+
+/// Draft an article from a Jira ticket using LLM, split into the fields of
+/// `NewArticle` along with the rendered markdown
+///
+/// `related_tickets` (picked by hand or via `fetch_linked_tickets`) are
+/// drafted into the same article, after `ticket`.
+///
+/// `ollama_url` is the base URL of whichever server `provider` selects
+/// (Ollama when omitted). Partial output is streamed to the UI as
+/// `draft-progress` events tagged with `draft_id` (defaults to the ticket
+/// key), which is also the id `cancel_draft` takes.
+///
+/// `context_length` is the model's context window in tokens (4096 when
+/// omitted). Tickets too long for it are condensed before drafting, with
+/// older comments summarized by the model.
+///
+/// The `reference_count` existing articles most relevant to the tickets (3
+/// when omitted, 0 to turn it off) are included in the prompt for the model
+/// to stay consistent with and cite. They are returned with the draft and
+/// kept in its provenance record.
+///
+/// With `language` set to another code than "en" (see `list_languages`),
+/// the draft is written in English first and then translated, so the
+/// fields still map to the template's sections. The provenance record
+/// keeps the English generation; the grounding check runs on the
+/// translation.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn draft_article(
+    ticket: JiraTicket,
+    related_tickets: Option<Vec<JiraTicket>>,
+    template_id: String,
+    ollama_url: String,
+    model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    draft_id: Option<String>,
+    reference_count: Option<usize>,
+    language: Option<String>,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
+) -> Result<ArticleDraft, AppError> {
+    let language = language.filter(|l| !l.eq_ignore_ascii_case(DEFAULT_LANGUAGE));
+    if let Some(language) = &language {
+        if translator::language_name(language).is_none() {
+            return Err(AppError::Internal(format!(
+                "Unsupported language: {}",
+                language
+            )));
+        }
+    }
+    let draft_id = draft_id.unwrap_or_else(|| ticket.key.clone());
+    let mut tickets = vec![ticket];
+    for related in related_tickets.unwrap_or_default() {
+        if !tickets.iter().any(|t| t.key == related.key) {
+            tickets.push(related);
+        }
+    }
+
+    // Get the template and the reference articles from the database
+    let pool = db.inner().clone();
+    let retrieval_tickets = tickets.clone();
+    let (template, references) = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+        let conn = pool.get()?;
+        let template = templates::get_template(&conn, &template_id)?;
+        let references = reference_articles(&conn, &retrieval_tickets, reference_count)?;
+        Ok((template, references))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+
+    let model = template.model.clone().unwrap_or(model);
+    let provider = llm_provider(
+        provider,
+        &ollama_url,
+        &model,
+        context_length,
+        &template.generation,
+    );
+    let mut on_token = progress_emitter(app, draft_id.clone());
+
+    let (mut draft, provenance) = run_cancellable(&drafts, &draft_id, async move {
+        let (draft, provenance) = drafter::draft(
+            &tickets,
+            &template,
+            &references,
+            provider.as_ref(),
+            &mut on_token,
+        )
+        .await?;
+        let Some(language) = language else {
+            return Ok((draft, provenance));
+        };
+
+        let (translated, _) = translator::translate(
+            &draft.content_markdown,
+            &draft.tags,
+            &language,
+            &template.field_mapping,
+            provider.as_ref(),
+            &mut on_token,
+        )
+        .await?;
+        // The flags of the English draft point at its lines. Checked against
+        // the tickets as the model saw them, placeholders included.
+        let (redacted, _) = sensitive_data::redact_tickets(&tickets);
+        let ungrounded = grounding::check(&translated.content_markdown, &redacted);
+        Ok((
+            ArticleDraft {
+                structured: draft.structured,
+                ungrounded,
+                references: draft.references,
+                redactions: draft.redactions,
+                warnings: draft.warnings,
+                ..translated
+            },
+            provenance,
+        ))
+    })
+    .await?;
+
+    // Keep what went into the draft; linked to the article on save
+    let pool = db.inner().clone();
+    let provenance_id = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+        let conn = pool.get()?;
+        Ok(provenance::insert_provenance(&conn, &provenance)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+    draft.provenance_id = Some(provenance_id);
+
+    Ok(draft)
+}
+
+/// Translate a saved article into `language` (see `list_languages`) and
+/// save the result as a sibling draft article linked to the original. An
+/// existing translation into that language is replaced. Code blocks,
+/// commands, paths and URLs are kept as they are.
+///
+/// Translations of a translation are made from the original. Streams and
+/// cancels like `draft_with_llm`; `draft_id` defaults to
+/// `translate-<article_id>-<language>`.
+///
+/// The translation gets a provenance record of its own, with the original's
+/// redactions and warnings. The source tickets are not at hand here, so a
+/// warning says the grounding check only covered the original.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn translate_article(
+    article_id: i64,
+    language: String,
+    ollama_url: String,
+    model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    draft_id: Option<String>,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
+) -> Result<Article, AppError> {
+    let language = language.to_lowercase();
+    let pool = db.inner().clone();
+    let (source, siblings, template, source_record) =
+        tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+            let conn = pool.get()?;
+            let siblings = articles::list_translations(&conn, article_id)?;
+            let source = siblings
+                .iter()
+                .find(|a| a.translation_of.is_none())
+                .cloned()
+                .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+            let template = match &source.template_id {
+                Some(id) => templates::get_template(&conn, id).ok(),
+                None => None,
+            };
+            let source_record = provenance::list_for_article(&conn, source.id)?
+                .into_iter()
+                .next();
+            Ok((source, siblings, template, source_record))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+
+    if source.language.eq_ignore_ascii_case(&language) {
+        return Err(AppError::Internal(format!(
+            "The article is already written in {}",
+            translator::language_name(&language).unwrap_or(&language)
+        )));
+    }
+
+    let draft_id = draft_id.unwrap_or_else(|| format!("translate-{}-{}", article_id, language));
+    let generation = template
+        .as_ref()
+        .map(|t| t.generation.clone())
+        .unwrap_or_default();
+    let model = template
+        .as_ref()
+        .and_then(|t| t.model.clone())
+        .unwrap_or(model);
+    let provider = llm_provider(provider, &ollama_url, &model, context_length, &generation);
+    let mapping = template.map(|t| t.field_mapping).unwrap_or_default();
+    let mut on_token = progress_emitter(app, draft_id.clone());
+
+    let markdown = source.content_markdown.clone();
+    let tags = source.tags.clone();
+    let target = language.clone();
+    let (draft, record) = run_cancellable(&drafts, &draft_id, async move {
+        translator::translate(
+            &markdown,
+            &tags,
+            &target,
+            &mapping,
+            provider.as_ref(),
+            &mut on_token,
+        )
+        .await
+    })
+    .await?;
+
+    let (redactions, mut warnings) = source_record
+        .map(|r| (r.redactions, r.warnings))
+        .unwrap_or_default();
+    warnings.push(format!(
+        "Translated from article {}: the grounding check ran on the original, not on this \
+         translation. Review it against the source tickets before publishing.",
+        source.id
+    ));
+    let record = NewDraftProvenance {
+        ticket_keys: source.source_ticket_keys.clone(),
+        template_id: source.template_id.clone(),
+        redactions,
+        warnings,
+        ..record
+    };
+
+    let translation = NewArticle {
+        ticket_key: source.ticket_key.clone(),
+        source_ticket_keys: source.source_ticket_keys.clone(),
+        title: draft.title,
+        problem: draft.problem,
+        solution: draft.solution,
+        expected_result: draft.expected_result,
+        prerequisites: draft.prerequisites,
+        additional_notes: draft.additional_notes,
+        tags: draft.tags,
+        content_markdown: draft.content_markdown,
+        template_id: source.template_id.clone(),
+        provenance_id: None,
+        language: Some(language.clone()),
+        translation_of: Some(source.id),
+    };
+    let existing = siblings
+        .iter()
+        .find(|a| a.translation_of.is_some() && a.language.eq_ignore_ascii_case(&language))
+        .map(|a| a.id);
+
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Article, AppError> {
+        let conn = pool.get()?;
+        let id = match existing {
+            Some(id) => {
+                articles::update_article(&conn, id, &translation)?;
+                id
+            }
+            None => articles::insert_article(&conn, &translation)?,
+        };
+        let provenance_id = provenance::insert_provenance(&conn, &record)?;
+        provenance::link_article(&conn, provenance_id, id)?;
+        Ok(articles::get_article(&conn, id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))?
+}
+
+/// Regenerate one section of an article (e.g. "Resolution") from the ticket,
+/// leaving the rest of `content_markdown` untouched. Returns the full article.
+///
+/// `provenance_id` is the record of the draft the article came from: its
+/// redaction mapping is reused, and any new redactions are added to it so
+/// `restore_redacted_values` can put them back too.
+///
+/// Streams and cancels like `draft_with_llm`; `draft_id` defaults to
+/// `<ticket key>-section`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn regenerate_section(
     ticket: JiraTicket,
     template_id: String,
+    content_markdown: String,
+    section_heading: String,
+    provenance_id: Option<i64>,
     ollama_url: String,
     model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    draft_id: Option<String>,
+    app: AppHandle,
     db: State<'_, DbPool>,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
 ) -> Result<String, AppError> {
-    // Get the template from the database
     let pool = db.inner().clone();
-    let template = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+    let (template, known) = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
         let conn = pool.get()?;
-        Ok(crate::db::templates::get_template(&conn, &template_id)?)
+        let known = match provenance_id {
+            Some(id) => provenance::get_provenance(&conn, id)?.redactions,
+            None => Vec::new(),
+        };
+        Ok((templates::get_template(&conn, &template_id)?, known))
     })
     .await
     .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
 
-    // Generate the article
-    let markdown = drafter::draft(&ticket, &template, &ollama_url, &model).await?;
+    let draft_id = draft_id.unwrap_or_else(|| format!("{}-section", ticket.key));
+    let model = template.model.clone().unwrap_or(model);
+    let provider = llm_provider(
+        provider,
+        &ollama_url,
+        &model,
+        context_length,
+        &template.generation,
+    );
+    let mut on_token = progress_emitter(app, draft_id.clone());
+
+    let known_count = known.len();
+    let (markdown, redactions) = run_cancellable(&drafts, &draft_id, async move {
+        drafter::regenerate_section(
+            &ticket,
+            &template,
+            &content_markdown,
+            &section_heading,
+            &known,
+            provider.as_ref(),
+            &mut on_token,
+        )
+        .await
+    })
+    .await?;
+
+    if let Some(id) = provenance_id.filter(|_| redactions.len() > known_count) {
+        let pool = db.inner().clone();
+        tokio::task::spawn_blocking(move || -> Result<(), AppError> {
+            let conn = pool.get()?;
+            Ok(provenance::update_redactions(&conn, id, &redactions)?)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+    }
 
     Ok(markdown)
 }
+
+/// Revise a saved article according to a free-form instruction, continuing
+/// the refinement conversation stored for it
+///
+/// Streams and cancels like `draft_with_llm`; `draft_id` defaults to
+/// `refine-<article_id>`. Returns the revised markdown without saving it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn refine_draft(
+    article_id: i64,
+    content_markdown: String,
+    instruction: String,
+    ollama_url: String,
+    model: String,
+    provider: Option<LlmProviderKind>,
+    context_length: Option<usize>,
+    draft_id: Option<String>,
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
+) -> Result<String, AppError> {
+    if instruction.trim().is_empty() {
+        return Err(AppError::Internal(
+            "Refinement instruction is empty".to_string(),
+        ));
+    }
+
+    // Load the template's system prompt and the conversation so far
+    let pool = db.inner().clone();
+    let (template, history) = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+        let conn = pool.get()?;
+        let article = articles::get_article(&conn, article_id)?;
+        let template = match article.template_id {
+            Some(template_id) => Some(templates::get_template(&conn, &template_id)?),
+            None => None,
+        };
+        let history: Vec<ChatMessage> = refinements::list_messages(&conn, article_id)?
+            .into_iter()
+            .map(|m| ChatMessage {
+                role: m.role,
+                content: m.content,
+            })
+            .collect();
+        Ok((template, history))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+
+    let (system_prompt, model, generation) = match template {
+        Some(template) => (
+            template.system_prompt,
+            template.model.unwrap_or(model),
+            template.generation,
+        ),
+        None => (
+            drafter::DEFAULT_REFINE_SYSTEM_PROMPT.to_string(),
+            model,
+            GenerationSettings::default(),
+        ),
+    };
+
+    let draft_id = draft_id.unwrap_or_else(|| format!("refine-{}", article_id));
+    let provider = llm_provider(provider, &ollama_url, &model, context_length, &generation);
+    let mut on_token = progress_emitter(app, draft_id.clone());
+
+    let task_instruction = instruction.clone();
+    let refined = run_cancellable(&drafts, &draft_id, async move {
+        drafter::refine(
+            &system_prompt,
+            &history,
+            &content_markdown,
+            &task_instruction,
+            provider.as_ref(),
+            &mut on_token,
+        )
+        .await
+    })
+    .await?;
+
+    // Record this pass so the next one builds on it
+    let pool = db.inner().clone();
+    let answer = refined.clone();
+    tokio::task::spawn_blocking(move || -> Result<(), AppError> {
+        let conn = pool.get()?;
+        refinements::insert_message(&conn, article_id, "user", &instruction)?;
+        refinements::insert_message(&conn, article_id, "assistant", &answer)?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
+
+    Ok(refined)
+}
+
+/// Get the refinement conversation stored for an article, oldest first
+#[tauri::command]
+pub async fn get_refinement_history(
+    article_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<Vec<RefinementMessage>, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<RefinementMessage>, AppError> {
+        let conn = pool.get()?;
+        Ok(refinements::list_messages(&conn, article_id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Get the provenance records of every generation an article was drafted
+/// from: prompts, model, settings, raw output and timing. Newest first.
+#[tauri::command]
+pub async fn get_draft_provenance(
+    article_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<Vec<DraftProvenance>, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<DraftProvenance>, AppError> {
+        let conn = pool.get()?;
+        Ok(provenance::list_for_article(&conn, article_id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Get the existing articles that were given to the model as reference
+/// material when the article was last drafted
+#[tauri::command]
+pub async fn get_article_references(
+    article_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<Vec<ArticleReference>, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Vec<ArticleReference>, AppError> {
+        let conn = pool.get()?;
+        let records = provenance::list_for_article(&conn, article_id)?;
+        Ok(records
+            .into_iter()
+            .next()
+            .map(|record| record.references)
+            .unwrap_or_default())
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Get one provenance record by the `provenance_id` returned with a draft,
+/// e.g. before the draft has been saved
+#[tauri::command]
+pub async fn get_provenance_record(
+    id: i64,
+    db: State<'_, DbPool>,
+) -> Result<DraftProvenance, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<DraftProvenance, AppError> {
+        let conn = pool.get()?;
+        Ok(provenance::get_provenance(&conn, id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Put the real values back into `content` in place of the placeholders the
+/// sensitive ticket data was redacted to when the draft was generated
+#[tauri::command]
+pub async fn restore_redacted_values(
+    content: String,
+    provenance_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<String, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<String, AppError> {
+        let conn = pool.get()?;
+        let record = provenance::get_provenance(&conn, provenance_id)?;
+        Ok(sensitive_data::restore(&content, &record.redactions))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Forget the refinement conversation of an article and start over
+#[tauri::command]
+pub async fn clear_refinement_history(
+    article_id: i64,
+    db: State<'_, DbPool>,
+) -> Result<(), AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<(), AppError> {
+        let conn = pool.get()?;
+        Ok(refinements::delete_messages(&conn, article_id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Abort an in-flight `draft_with_llm` or `refine_draft` call. Returns false if nothing was running.
+#[tauri::command]
+pub async fn cancel_draft(
+    draft_id: String,
+    drafts: State<'_, Mutex<ActiveDrafts>>,
+) -> Result<bool, AppError> {
+    let mut drafts = drafts
+        .lock()
+        .map_err(|e| AppError::Internal(format!("Failed to lock drafts: {}", e)))?;
+
+    match drafts.handles.remove(&draft_id) {
+        Some(handle) => {
+            handle.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
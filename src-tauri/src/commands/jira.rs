@@ -1,6 +1,6 @@
 use crate::error::AppError;
-use crate::models::JiraTicket;
-use crate::services::{jira::JiraClient, tokens};
+use crate::models::{AuthMode, Credentials, JiraTicket};
+use crate::services::{atlassian, jira::JiraClient, tokens};
 use tauri::State;
 
 // Simple settings storage for URLs (PATs go in keychain)
@@ -16,20 +16,37 @@ impl Default for JiraSettings {
     }
 }
 
+/// Test the connection and return the auth mode Jira accepted. `pat` is
+/// the PAT for Server / Data Center or the API token for Cloud, which also
+/// needs `email`. Without `auth_mode` the deployment type is detected.
 #[tauri::command]
-pub async fn test_jira_connection(base_url: String, pat: String) -> Result<bool, AppError> {
-    let client = JiraClient::new(base_url, pat);
-    client.test_connection().await
+pub async fn test_jira_connection(
+    base_url: String,
+    pat: String,
+    email: Option<String>,
+    auth_mode: Option<AuthMode>,
+) -> Result<AuthMode, AppError> {
+    let auth_mode = auth_mode.unwrap_or_default();
+    let credentials = detect_credentials(&base_url, &pat, email.as_deref(), auth_mode).await?;
+    Ok(credentials.mode())
 }
 
 #[tauri::command]
 pub async fn save_jira_config(
     base_url: String,
     pat: String,
+    email: Option<String>,
+    auth_mode: Option<AuthMode>,
     settings: State<'_, Mutex<JiraSettings>>,
 ) -> Result<(), AppError> {
-    // Store PAT in keychain
-    tokens::store_token("jira", &pat)?;
+    // Store credentials in keychain, probing Jira first if the mode is not known
+    let credentials = match auth_mode.unwrap_or_default() {
+        AuthMode::Auto => {
+            detect_credentials(&base_url, &pat, email.as_deref(), AuthMode::Auto).await?
+        }
+        mode => atlassian::candidates(&base_url, mode, email.as_deref(), &pat)?.remove(0),
+    };
+    tokens::store_credentials("jira", &credentials)?;
 
     // Store base URL in app state
     let mut settings = settings.lock()
@@ -39,6 +56,21 @@ pub async fn save_jira_config(
     Ok(())
 }
 
+/// The first of the candidate credentials Jira accepts
+async fn detect_credentials(
+    base_url: &str,
+    token: &str,
+    email: Option<&str>,
+    auth_mode: AuthMode,
+) -> Result<Credentials, AppError> {
+    let candidates = atlassian::candidates(base_url, auth_mode, email, token)?;
+    atlassian::detect("jira", candidates, |credentials| {
+        let client = JiraClient::new(base_url.to_string(), credentials);
+        async move { client.test_connection().await }
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn fetch_jira_ticket(
     key: String,
@@ -53,11 +85,46 @@ pub async fn fetch_jira_ticket(
             .ok_or_else(|| AppError::Internal("Jira not configured".to_string()))?
     };
 
-    let pat = tokens::get_token("jira")?;
-    let client = JiraClient::new(base_url, pat);
+    let credentials = tokens::get_credentials("jira")?;
+    let client = JiraClient::new(base_url, credentials);
     client.get_ticket(&key).await
 }
 
+/// Fetch every ticket linked from `key`, for drafting one article from a
+/// set of related tickets. Links the user cannot see are skipped.
+#[tauri::command]
+pub async fn fetch_linked_tickets(
+    key: String,
+    settings: State<'_, Mutex<JiraSettings>>,
+) -> Result<Vec<JiraTicket>, AppError> {
+    let base_url = {
+        let settings = settings.lock()
+            .map_err(|e| AppError::Internal(format!("Failed to lock settings: {}", e)))?;
+        settings
+            .base_url
+            .clone()
+            .ok_or_else(|| AppError::Internal("Jira not configured".to_string()))?
+    };
+
+    let credentials = tokens::get_credentials("jira")?;
+    let client = JiraClient::new(base_url, credentials);
+    let ticket = client.get_ticket(&key).await?;
+
+    let mut linked = Vec::new();
+    for link in &ticket.linked_issues {
+        // The same issue can be linked more than once, with different link types
+        if link.key == ticket.key || linked.iter().any(|t: &JiraTicket| t.key == link.key) {
+            continue;
+        }
+        match client.get_ticket(&link.key).await {
+            Ok(linked_ticket) => linked.push(linked_ticket),
+            Err(e) => log::warn!("Skipping linked ticket {}: {}", link.key, e),
+        }
+    }
+
+    Ok(linked)
+}
+
 #[tauri::command]
 pub async fn search_jira_tickets(
     query: String,
@@ -72,8 +139,8 @@ pub async fn search_jira_tickets(
             .ok_or_else(|| AppError::Internal("Jira not configured".to_string()))?
     };
 
-    let pat = tokens::get_token("jira")?;
-    let client = JiraClient::new(base_url, pat);
+    let credentials = tokens::get_credentials("jira")?;
+    let client = JiraClient::new(base_url, credentials);
     client.search_tickets(&query).await
 }
 
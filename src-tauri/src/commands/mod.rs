@@ -1,4 +1,5 @@
 pub mod articles;
+pub mod batch;
 pub mod confluence;
 pub mod drafting;
 pub mod jira;
@@ -11,6 +12,7 @@ pub fn ping() -> String {
 
 // Re-export commands for easy handler registration
 pub use articles::*;
+pub use batch::*;
 pub use confluence::*;
 pub use drafting::*;
 pub use jira::*;
@@ -1,6 +1,11 @@
+use super::drafting::llm_provider;
 use crate::db::{templates, DbPool};
 use crate::error::AppError;
-use crate::models::Template;
+use crate::models::{
+    ArticleField, GenerationSettings, JiraTicket, LlmProviderKind, Template, TemplateRecommendation,
+};
+use crate::services::template_recommender;
+use std::collections::BTreeMap;
 use tauri::State;
 
 #[tauri::command]
@@ -14,6 +19,42 @@ pub async fn list_templates(db: State<'_, DbPool>) -> Result<Vec<Template>, AppE
     .map_err(|e| AppError::Internal(e.to_string()))?
 }
 
+/// Set which article field each `output_structure` heading of a template fills
+#[tauri::command]
+pub async fn update_template_field_mapping(
+    id: String,
+    field_mapping: BTreeMap<String, ArticleField>,
+    db: State<'_, DbPool>,
+) -> Result<Template, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Template, AppError> {
+        let conn = pool.get()?;
+        templates::update_field_mapping(&conn, &id, &field_mapping)?;
+        Ok(templates::get_template(&conn, &id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// Set the sampling settings a template drafts with, and the model it uses
+/// instead of the configured one. An empty or missing `model` clears the override.
+#[tauri::command]
+pub async fn update_template_generation_settings(
+    id: String,
+    generation: GenerationSettings,
+    model: Option<String>,
+    db: State<'_, DbPool>,
+) -> Result<Template, AppError> {
+    let pool = db.inner().clone();
+    tokio::task::spawn_blocking(move || -> Result<Template, AppError> {
+        let conn = pool.get()?;
+        templates::update_generation_settings(&conn, &id, &generation, model.as_deref())?;
+        Ok(templates::get_template(&conn, &id)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
 #[tauri::command]
 pub async fn get_template(id: String, db: State<'_, DbPool>) -> Result<Template, AppError> {
     let pool = db.inner().clone();
@@ -24,3 +65,52 @@ pub async fn get_template(id: String, db: State<'_, DbPool>) -> Result<Template,
     .await
     .map_err(|e| AppError::Internal(e.to_string()))?
 }
+
+/// Rank the templates for a ticket, best match first. With `use_llm`, the
+/// configured model gets a say on top of the heuristic ranking; if it is
+/// unreachable the heuristic ranking is returned on its own.
+#[tauri::command]
+pub async fn recommend_templates(
+    ticket: JiraTicket,
+    use_llm: Option<bool>,
+    ollama_url: Option<String>,
+    model: Option<String>,
+    provider: Option<LlmProviderKind>,
+    db: State<'_, DbPool>,
+) -> Result<Vec<TemplateRecommendation>, AppError> {
+    let pool = db.inner().clone();
+    let all_templates = tokio::task::spawn_blocking(move || -> Result<Vec<Template>, AppError> {
+        let conn = pool.get()?;
+        Ok(templates::list_templates(&conn)?)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    let ranked = template_recommender::recommend(&ticket, &all_templates);
+
+    let (Some(true), Some(ollama_url), Some(model)) = (use_llm, ollama_url, model) else {
+        return Ok(ranked);
+    };
+
+    let provider = llm_provider(
+        provider,
+        &ollama_url,
+        &model,
+        None,
+        &GenerationSettings::default(),
+    );
+    match template_recommender::refine_with_llm(
+        &ticket,
+        &all_templates,
+        ranked.clone(),
+        provider.as_ref(),
+    )
+    .await
+    {
+        Ok(refined) => Ok(refined),
+        Err(e) => {
+            log::warn!("Template recommendation without LLM pass: {}", e);
+            Ok(ranked)
+        }
+    }
+}
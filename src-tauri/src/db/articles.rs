@@ -1,16 +1,19 @@
-use crate::models::{Article, ArticleStatus, NewArticle};
-use rusqlite::{params, Connection, Result as SqliteResult};
+use crate::models::{Article, ArticleStatus, NewArticle, DEFAULT_LANGUAGE};
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
 
 pub fn insert_article(conn: &Connection, article: &NewArticle) -> SqliteResult<i64> {
     let tags_json = serde_json::to_string(&article.tags).unwrap_or_else(|_| "[]".to_string());
+    let source_keys = article.source_keys();
+    let sources_json = serde_json::to_string(&source_keys).unwrap_or_else(|_| "[]".to_string());
 
     conn.execute(
         "INSERT INTO kb_articles (
             ticket_key, title, problem, solution, expected_result,
-            prerequisites, additional_notes, tags, content_markdown, template_id
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            prerequisites, additional_notes, tags, content_markdown, template_id,
+            source_ticket_keys, language, translation_of
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
         params![
-            article.ticket_key,
+            source_keys.first(),
             article.title,
             article.problem,
             article.solution,
@@ -20,6 +23,9 @@ pub fn insert_article(conn: &Connection, article: &NewArticle) -> SqliteResult<i
             tags_json,
             article.content_markdown,
             article.template_id,
+            sources_json,
+            article.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+            article.translation_of,
         ],
     )?;
 
@@ -31,37 +37,12 @@ pub fn get_article(conn: &Connection, id: i64) -> SqliteResult<Article> {
         "SELECT id, ticket_key, title, problem, solution, expected_result,
                 prerequisites, additional_notes, tags, content_markdown, status,
                 confluence_page_id, confluence_url, confluence_space_key,
-                quality_score, template_id, created_at, updated_at
+                quality_score, template_id, created_at, updated_at, source_ticket_keys,
+                language, translation_of
          FROM kb_articles WHERE id = ?1",
     )?;
 
-    stmt.query_row([id], |row| {
-        let tags_json: String = row.get(8)?;
-        let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-        let status_str: String = row.get(10)?;
-        let status = ArticleStatus::from_str(&status_str).unwrap_or(ArticleStatus::Draft);
-
-        Ok(Article {
-            id: row.get(0)?,
-            ticket_key: row.get(1)?,
-            title: row.get(2)?,
-            problem: row.get(3)?,
-            solution: row.get(4)?,
-            expected_result: row.get(5)?,
-            prerequisites: row.get(6)?,
-            additional_notes: row.get(7)?,
-            tags,
-            content_markdown: row.get(9)?,
-            status,
-            confluence_page_id: row.get(11)?,
-            confluence_url: row.get(12)?,
-            confluence_space_key: row.get(13)?,
-            quality_score: row.get(14)?,
-            template_id: row.get(15)?,
-            created_at: row.get(16)?,
-            updated_at: row.get(17)?,
-        })
-    })
+    stmt.query_row([id], row_to_article)
 }
 
 pub fn list_articles(
@@ -73,7 +54,8 @@ pub fn list_articles(
             "SELECT id, ticket_key, title, problem, solution, expected_result,
                     prerequisites, additional_notes, tags, content_markdown, status,
                     confluence_page_id, confluence_url, confluence_space_key,
-                    quality_score, template_id, created_at, updated_at
+                    quality_score, template_id, created_at, updated_at, source_ticket_keys,
+                    language, translation_of
              FROM kb_articles WHERE status = ?1 ORDER BY updated_at DESC",
             vec![status as &dyn rusqlite::ToSql],
         )
@@ -82,56 +64,87 @@ pub fn list_articles(
             "SELECT id, ticket_key, title, problem, solution, expected_result,
                     prerequisites, additional_notes, tags, content_markdown, status,
                     confluence_page_id, confluence_url, confluence_space_key,
-                    quality_score, template_id, created_at, updated_at
+                    quality_score, template_id, created_at, updated_at, source_ticket_keys,
+                    language, translation_of
              FROM kb_articles ORDER BY updated_at DESC",
             vec![],
         )
     };
 
     let mut stmt = conn.prepare(query)?;
-    let articles = stmt.query_map(params.as_slice(), |row| {
-        let tags_json: String = row.get(8)?;
-        let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-        let status_str: String = row.get(10)?;
-        let status = ArticleStatus::from_str(&status_str).unwrap_or(ArticleStatus::Draft);
-
-        Ok(Article {
-            id: row.get(0)?,
-            ticket_key: row.get(1)?,
-            title: row.get(2)?,
-            problem: row.get(3)?,
-            solution: row.get(4)?,
-            expected_result: row.get(5)?,
-            prerequisites: row.get(6)?,
-            additional_notes: row.get(7)?,
-            tags,
-            content_markdown: row.get(9)?,
-            status,
-            confluence_page_id: row.get(11)?,
-            confluence_url: row.get(12)?,
-            confluence_space_key: row.get(13)?,
-            quality_score: row.get(14)?,
-            template_id: row.get(15)?,
-            created_at: row.get(16)?,
-            updated_at: row.get(17)?,
-        })
-    })?;
+    let articles = stmt.query_map(params.as_slice(), row_to_article)?;
+
+    articles.collect()
+}
+
+/// Every language version of an article: the original it was translated
+/// from (or the article itself) first, then its translations
+pub fn list_translations(conn: &Connection, id: i64) -> SqliteResult<Vec<Article>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, ticket_key, title, problem, solution, expected_result,
+                prerequisites, additional_notes, tags, content_markdown, status,
+                confluence_page_id, confluence_url, confluence_space_key,
+                quality_score, template_id, created_at, updated_at, source_ticket_keys,
+                language, translation_of
+         FROM kb_articles
+         WHERE ?1 IN (id, translation_of)
+            OR id = (SELECT translation_of FROM kb_articles WHERE id = ?1)
+            OR translation_of = (SELECT translation_of FROM kb_articles WHERE id = ?1)
+         ORDER BY translation_of IS NOT NULL, id",
+    )?;
 
+    let articles = stmt.query_map([id], row_to_article)?;
     articles.collect()
 }
 
+fn row_to_article(row: &Row) -> SqliteResult<Article> {
+    let tags_json: String = row.get(8)?;
+    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    let sources_json: String = row.get(18)?;
+    let source_ticket_keys: Vec<String> = serde_json::from_str(&sources_json).unwrap_or_default();
+    let status_str: String = row.get(10)?;
+    let status = ArticleStatus::from_str(&status_str).unwrap_or(ArticleStatus::Draft);
+
+    Ok(Article {
+        id: row.get(0)?,
+        ticket_key: row.get(1)?,
+        title: row.get(2)?,
+        problem: row.get(3)?,
+        solution: row.get(4)?,
+        expected_result: row.get(5)?,
+        prerequisites: row.get(6)?,
+        additional_notes: row.get(7)?,
+        tags,
+        content_markdown: row.get(9)?,
+        status,
+        confluence_page_id: row.get(11)?,
+        confluence_url: row.get(12)?,
+        confluence_space_key: row.get(13)?,
+        quality_score: row.get(14)?,
+        template_id: row.get(15)?,
+        created_at: row.get(16)?,
+        updated_at: row.get(17)?,
+        source_ticket_keys,
+        language: row.get(19)?,
+        translation_of: row.get(20)?,
+    })
+}
+
 pub fn update_article(conn: &Connection, id: i64, article: &NewArticle) -> SqliteResult<()> {
     let tags_json = serde_json::to_string(&article.tags).unwrap_or_else(|_| "[]".to_string());
+    let source_keys = article.source_keys();
+    let sources_json = serde_json::to_string(&source_keys).unwrap_or_else(|_| "[]".to_string());
 
     conn.execute(
         "UPDATE kb_articles SET
             ticket_key = ?1, title = ?2, problem = ?3, solution = ?4,
             expected_result = ?5, prerequisites = ?6, additional_notes = ?7,
             tags = ?8, content_markdown = ?9, template_id = ?10,
+            source_ticket_keys = ?11, language = COALESCE(?13, language),
             updated_at = datetime('now')
-         WHERE id = ?11",
+         WHERE id = ?12",
         params![
-            article.ticket_key,
+            source_keys.first(),
             article.title,
             article.problem,
             article.solution,
@@ -141,16 +154,42 @@ pub fn update_article(conn: &Connection, id: i64, article: &NewArticle) -> Sqlit
             tags_json,
             article.content_markdown,
             article.template_id,
+            sources_json,
             id,
+            article.language,
         ],
     )?;
 
     Ok(())
 }
 
+/// Delete an article along with everything recorded about it. Its
+/// provenance goes too, since the redaction mapping in there holds the
+/// sensitive values the draft was redacted from.
 pub fn delete_article(conn: &Connection, id: i64) -> SqliteResult<()> {
-    conn.execute("DELETE FROM kb_articles WHERE id = ?1", [id])?;
-    Ok(())
+    let tx = conn.unchecked_transaction()?;
+
+    // Translations of a deleted article stay linked to each other, through
+    // the oldest of them
+    let heir: Option<i64> = tx.query_row(
+        "SELECT MIN(id) FROM kb_articles WHERE translation_of = ?1",
+        [id],
+        |row| row.get(0),
+    )?;
+    if let Some(heir) = heir {
+        tx.execute(
+            "UPDATE kb_articles SET translation_of = ?1 WHERE translation_of = ?2 AND id != ?1",
+            params![heir, id],
+        )?;
+        tx.execute("UPDATE kb_articles SET translation_of = NULL WHERE id = ?1", [heir])?;
+    }
+
+    super::refinements::delete_messages(&tx, id)?;
+    super::embeddings::delete_embedding(&tx, id)?;
+    super::provenance::delete_for_article(&tx, id)?;
+    super::batches::detach_article(&tx, id)?;
+    tx.execute("DELETE FROM kb_articles WHERE id = ?1", [id])?;
+    tx.commit()
 }
 
 pub fn update_article_quality_score(conn: &Connection, id: i64, score: u8) -> SqliteResult<()> {
@@ -160,3 +199,17 @@ pub fn update_article_quality_score(conn: &Connection, id: i64, score: u8) -> Sq
     )?;
     Ok(())
 }
+
+/// Every tag used by an article, with the number of articles using it
+pub fn tag_counts(conn: &Connection) -> SqliteResult<Vec<(String, usize)>> {
+    let mut stmt = conn.prepare(
+        "SELECT tag.value, COUNT(DISTINCT kb_articles.id)
+         FROM kb_articles, json_each(kb_articles.tags) AS tag
+         WHERE json_valid(kb_articles.tags) AND tag.type = 'text'
+         GROUP BY tag.value
+         ORDER BY COUNT(DISTINCT kb_articles.id) DESC, tag.value",
+    )?;
+
+    let counts = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?;
+    counts.collect()
+}
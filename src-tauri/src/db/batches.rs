@@ -0,0 +1,270 @@
+use crate::models::{
+    BatchJob, BatchRun, BatchStatus, FlaggedSection, JobStatus, LlmProviderKind, NewBatchRun,
+};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+
+const BATCH_COLUMNS: &str =
+    "b.id, b.jql, b.template_id, b.jira_url, b.provider, b.llm_url, b.model,
+    b.context_length, b.concurrency, b.max_attempts, b.status, b.created_at,
+    (SELECT COUNT(*) FROM batch_jobs j WHERE j.batch_id = b.id),
+    (SELECT COUNT(*) FROM batch_jobs j WHERE j.batch_id = b.id AND j.status = 'done'),
+    (SELECT COUNT(*) FROM batch_jobs j WHERE j.batch_id = b.id AND j.status = 'failed')";
+
+const JOB_COLUMNS: &str =
+    "id, batch_id, ticket_key, status, attempts, last_error, article_id, next_attempt_at, updated_at,
+    warnings, ungrounded";
+
+/// Store a batch with one queued job per ticket. Duplicate keys are dropped.
+pub fn create_batch(
+    conn: &Connection,
+    batch: &NewBatchRun,
+    ticket_keys: &[String],
+) -> SqliteResult<i64> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO batch_runs (
+            jql, template_id, jira_url, provider, llm_url, model, context_length,
+            concurrency, max_attempts
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            batch.jql,
+            batch.template_id,
+            batch.jira_url,
+            batch.provider.as_str(),
+            batch.llm_url,
+            batch.model,
+            batch.context_length,
+            batch.concurrency,
+            batch.max_attempts,
+        ],
+    )?;
+    let batch_id = tx.last_insert_rowid();
+
+    for key in ticket_keys {
+        tx.execute(
+            "INSERT OR IGNORE INTO batch_jobs (batch_id, ticket_key) VALUES (?1, ?2)",
+            params![batch_id, key],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(batch_id)
+}
+
+pub fn get_batch(conn: &Connection, id: i64) -> SqliteResult<BatchRun> {
+    let sql = format!("SELECT {} FROM batch_runs b WHERE b.id = ?1", BATCH_COLUMNS);
+    conn.query_row(&sql, [id], row_to_batch)
+}
+
+/// All batches, newest first
+pub fn list_batches(conn: &Connection) -> SqliteResult<Vec<BatchRun>> {
+    let sql = format!(
+        "SELECT {} FROM batch_runs b ORDER BY b.id DESC",
+        BATCH_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let batches = stmt.query_map([], row_to_batch)?;
+    batches.collect()
+}
+
+/// Batches that were still running when the app last stopped
+pub fn list_running_batches(conn: &Connection) -> SqliteResult<Vec<BatchRun>> {
+    let sql = format!(
+        "SELECT {} FROM batch_runs b WHERE b.status = 'running' ORDER BY b.id ASC",
+        BATCH_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let batches = stmt.query_map([], row_to_batch)?;
+    batches.collect()
+}
+
+/// Stop a batch: every job that has not finished is cancelled
+pub fn cancel_batch(conn: &Connection, id: i64) -> SqliteResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE batch_jobs SET status = 'cancelled', next_attempt_at = NULL, updated_at = datetime('now')
+         WHERE batch_id = ?1 AND status IN ('queued', 'running')",
+        [id],
+    )?;
+    tx.execute(
+        "UPDATE batch_runs SET status = 'cancelled' WHERE id = ?1",
+        [id],
+    )?;
+    tx.commit()
+}
+
+/// Queue the failed and cancelled jobs of a batch again with fresh attempts
+pub fn retry_failed_jobs(conn: &Connection, id: i64) -> SqliteResult<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let requeued = tx.execute(
+        "UPDATE batch_jobs SET status = 'queued', attempts = 0, next_attempt_at = NULL,
+                updated_at = datetime('now')
+         WHERE batch_id = ?1 AND status IN ('failed', 'cancelled')",
+        [id],
+    )?;
+    tx.execute(
+        "UPDATE batch_runs SET status = 'running' WHERE id = ?1",
+        [id],
+    )?;
+    tx.commit()?;
+    Ok(requeued)
+}
+
+/// Jobs left running by a previous session go back into the queue. The
+/// attempt they were on is not counted against them.
+pub fn requeue_interrupted_jobs(conn: &Connection) -> SqliteResult<usize> {
+    conn.execute(
+        "UPDATE batch_jobs SET status = 'queued', attempts = MAX(attempts - 1, 0),
+                updated_at = datetime('now')
+         WHERE status = 'running'",
+        [],
+    )
+}
+
+pub fn list_jobs(conn: &Connection, batch_id: i64) -> SqliteResult<Vec<BatchJob>> {
+    let sql = format!(
+        "SELECT {} FROM batch_jobs WHERE batch_id = ?1 ORDER BY id ASC",
+        JOB_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let jobs = stmt.query_map([batch_id], row_to_job)?;
+    jobs.collect()
+}
+
+pub fn get_job(conn: &Connection, id: i64) -> SqliteResult<BatchJob> {
+    let sql = format!("SELECT {} FROM batch_jobs WHERE id = ?1", JOB_COLUMNS);
+    conn.query_row(&sql, [id], row_to_job)
+}
+
+/// Mark the next job that is due as running and count the attempt. Done in
+/// one statement so two workers never pick the same job.
+pub fn claim_next_job(conn: &Connection, batch_id: i64) -> SqliteResult<Option<BatchJob>> {
+    let sql = format!(
+        "UPDATE batch_jobs SET status = 'running', attempts = attempts + 1,
+                next_attempt_at = NULL, updated_at = datetime('now')
+         WHERE id = (
+             SELECT id FROM batch_jobs
+             WHERE batch_id = ?1 AND status = 'queued'
+               AND (next_attempt_at IS NULL OR next_attempt_at <= datetime('now'))
+             ORDER BY id ASC LIMIT 1
+         )
+         RETURNING {}",
+        JOB_COLUMNS
+    );
+    conn.query_row(&sql, [batch_id], row_to_job).optional()
+}
+
+/// Whether the batch still has jobs waiting for a retry or being worked on
+pub fn has_unfinished_jobs(conn: &Connection, batch_id: i64) -> SqliteResult<bool> {
+    conn.query_row(
+        "SELECT EXISTS (
+             SELECT 1 FROM batch_jobs WHERE batch_id = ?1 AND status IN ('queued', 'running')
+         )",
+        [batch_id],
+        |row| row.get(0),
+    )
+}
+
+/// Mark a running batch finished if none of its jobs are left to do, in one
+/// statement so a concurrent retry cannot slip in between. Returns whether
+/// the batch has nothing left to do.
+pub fn finish_batch(conn: &Connection, id: i64) -> SqliteResult<bool> {
+    conn.execute(
+        "UPDATE batch_runs SET status = 'finished'
+         WHERE id = ?1 AND status = 'running' AND NOT EXISTS (
+             SELECT 1 FROM batch_jobs WHERE batch_id = ?1 AND status IN ('queued', 'running')
+         )",
+        [id],
+    )?;
+    Ok(!has_unfinished_jobs(conn, id)?)
+}
+
+pub fn complete_job(
+    conn: &Connection,
+    id: i64,
+    article_id: i64,
+    warnings: &[String],
+    ungrounded: &[FlaggedSection],
+) -> SqliteResult<()> {
+    let warnings_json = serde_json::to_string(warnings).unwrap_or_else(|_| "[]".to_string());
+    let ungrounded_json = serde_json::to_string(ungrounded).unwrap_or_else(|_| "[]".to_string());
+    conn.execute(
+        "UPDATE batch_jobs SET status = 'done', article_id = ?1, last_error = NULL,
+                warnings = ?2, ungrounded = ?3, updated_at = datetime('now')
+         WHERE id = ?4",
+        params![article_id, warnings_json, ungrounded_json, id],
+    )?;
+    Ok(())
+}
+
+/// Forget a deleted article on the jobs that drafted it
+pub fn detach_article(conn: &Connection, article_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE batch_jobs SET article_id = NULL WHERE article_id = ?1",
+        [article_id],
+    )?;
+    Ok(())
+}
+
+/// Record a failed attempt. With `retry_in_secs` the job is queued again
+/// once that much time has passed; without it the job is given up on.
+pub fn fail_job(
+    conn: &Connection,
+    id: i64,
+    error: &str,
+    retry_in_secs: Option<u64>,
+) -> SqliteResult<()> {
+    match retry_in_secs {
+        Some(secs) => conn.execute(
+            "UPDATE batch_jobs SET status = 'queued', last_error = ?1,
+                    next_attempt_at = datetime('now', ?2), updated_at = datetime('now')
+             WHERE id = ?3",
+            params![error, format!("+{} seconds", secs), id],
+        )?,
+        None => conn.execute(
+            "UPDATE batch_jobs SET status = 'failed', last_error = ?1, updated_at = datetime('now')
+             WHERE id = ?2",
+            params![error, id],
+        )?,
+    };
+    Ok(())
+}
+
+fn row_to_batch(row: &Row) -> SqliteResult<BatchRun> {
+    let provider: String = row.get(4)?;
+    let status: String = row.get(10)?;
+    Ok(BatchRun {
+        id: row.get(0)?,
+        jql: row.get(1)?,
+        template_id: row.get(2)?,
+        jira_url: row.get(3)?,
+        provider: LlmProviderKind::from_str(&provider).unwrap_or_default(),
+        llm_url: row.get(5)?,
+        model: row.get(6)?,
+        context_length: row.get(7)?,
+        concurrency: row.get(8)?,
+        max_attempts: row.get(9)?,
+        status: BatchStatus::from_str(&status).unwrap_or(BatchStatus::Finished),
+        created_at: row.get(11)?,
+        total_jobs: row.get(12)?,
+        completed_jobs: row.get(13)?,
+        failed_jobs: row.get(14)?,
+    })
+}
+
+fn row_to_job(row: &Row) -> SqliteResult<BatchJob> {
+    let status: String = row.get(3)?;
+    Ok(BatchJob {
+        id: row.get(0)?,
+        batch_id: row.get(1)?,
+        ticket_key: row.get(2)?,
+        status: JobStatus::from_str(&status).unwrap_or(JobStatus::Failed),
+        attempts: row.get(4)?,
+        last_error: row.get(5)?,
+        article_id: row.get(6)?,
+        next_attempt_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        warnings: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+        ungrounded: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
+    })
+}
@@ -0,0 +1,72 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+/// Embedding of an article as stored, with the hash of the text it was computed from
+#[derive(Debug, Clone)]
+pub struct StoredEmbedding {
+    pub article_id: i64,
+    pub content_hash: String,
+    pub vector: Vec<f32>,
+}
+
+pub fn upsert_embedding(
+    conn: &Connection,
+    article_id: i64,
+    model: &str,
+    content_hash: &str,
+    vector: &[f32],
+) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO article_embeddings (article_id, model, content_hash, dimensions, vector)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(article_id) DO UPDATE SET
+            model = excluded.model,
+            content_hash = excluded.content_hash,
+            dimensions = excluded.dimensions,
+            vector = excluded.vector,
+            updated_at = datetime('now')",
+        params![
+            article_id,
+            model,
+            content_hash,
+            vector.len() as i64,
+            encode_vector(vector),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Embeddings computed with `model`. Vectors of other models are not comparable.
+pub fn list_embeddings(conn: &Connection, model: &str) -> SqliteResult<Vec<StoredEmbedding>> {
+    let mut stmt = conn.prepare(
+        "SELECT article_id, content_hash, vector FROM article_embeddings WHERE model = ?1",
+    )?;
+
+    let embeddings = stmt.query_map([model], |row| {
+        let blob: Vec<u8> = row.get(2)?;
+        Ok(StoredEmbedding {
+            article_id: row.get(0)?,
+            content_hash: row.get(1)?,
+            vector: decode_vector(&blob),
+        })
+    })?;
+
+    embeddings.collect()
+}
+
+pub fn delete_embedding(conn: &Connection, article_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM article_embeddings WHERE article_id = ?1",
+        [article_id],
+    )?;
+    Ok(())
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
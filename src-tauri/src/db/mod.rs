@@ -1,4 +1,8 @@
 pub mod articles;
+pub mod batches;
+pub mod embeddings;
+pub mod provenance;
+pub mod refinements;
 pub mod templates;
 
 use r2d2::Pool;
@@ -38,6 +42,58 @@ fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     let migration_001 = include_str!("../../migrations/001_initial.sql");
     apply_migration(conn, "001_initial.sql", migration_001)?;
 
+    // Migration 002: Refinement conversation history
+    let migration_002 = include_str!("../../migrations/002_refinement_history.sql");
+    apply_migration(conn, "002_refinement_history.sql", migration_002)?;
+
+    // Migration 003: Multiple source tickets per article
+    let migration_003 = include_str!("../../migrations/003_article_sources.sql");
+    apply_migration(conn, "003_article_sources.sql", migration_003)?;
+
+    // Migration 004: Section-to-field mapping per template
+    let migration_004 = include_str!("../../migrations/004_template_field_mapping.sql");
+    apply_migration(conn, "004_template_field_mapping.sql", migration_004)?;
+
+    // Migration 005: Generation settings and model override per template
+    let migration_005 = include_str!("../../migrations/005_template_generation_settings.sql");
+    apply_migration(conn, "005_template_generation_settings.sql", migration_005)?;
+
+    // Migration 006: Provenance records of draft generations
+    let migration_006 = include_str!("../../migrations/006_draft_provenance.sql");
+    apply_migration(conn, "006_draft_provenance.sql", migration_006)?;
+
+    // Migration 007: Background batch drafting from JQL searches
+    let migration_007 = include_str!("../../migrations/007_batch_queue.sql");
+    apply_migration(conn, "007_batch_queue.sql", migration_007)?;
+
+    // Migration 008: Article embeddings for duplicate detection
+    let migration_008 = include_str!("../../migrations/008_article_embeddings.sql");
+    apply_migration(conn, "008_article_embeddings.sql", migration_008)?;
+
+    // Migration 009: Reference articles retrieved for each draft
+    let migration_009 = include_str!("../../migrations/009_draft_references.sql");
+    apply_migration(conn, "009_draft_references.sql", migration_009)?;
+
+    // Migration 010: Placeholders used to redact sensitive ticket data
+    let migration_010 = include_str!("../../migrations/010_draft_redactions.sql");
+    apply_migration(conn, "010_draft_redactions.sql", migration_010)?;
+
+    // Migration 011: Article language and links between translations
+    let migration_011 = include_str!("../../migrations/011_article_translations.sql");
+    apply_migration(conn, "011_article_translations.sql", migration_011)?;
+
+    // Migration 012: Built-in Post-Mortem template
+    let migration_012 = include_str!("../../migrations/012_post_mortem_template.sql");
+    apply_migration(conn, "012_post_mortem_template.sql", migration_012)?;
+
+    // Migration 013: Warnings about the source tickets of a draft
+    let migration_013 = include_str!("../../migrations/013_draft_warnings.sql");
+    apply_migration(conn, "013_draft_warnings.sql", migration_013)?;
+
+    // Migration 014: Warnings and ungrounded details of batch drafts
+    let migration_014 = include_str!("../../migrations/014_batch_job_warnings.sql");
+    apply_migration(conn, "014_batch_job_warnings.sql", migration_014)?;
+
     Ok(())
 }
 
@@ -0,0 +1,119 @@
+use crate::models::{DraftProvenance, NewDraftProvenance, Redaction};
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+
+pub fn insert_provenance(conn: &Connection, provenance: &NewDraftProvenance) -> SqliteResult<i64> {
+    let keys_json =
+        serde_json::to_string(&provenance.ticket_keys).unwrap_or_else(|_| "[]".to_string());
+    let options_json =
+        serde_json::to_string(&provenance.options).unwrap_or_else(|_| "{}".to_string());
+    let references_json =
+        serde_json::to_string(&provenance.references).unwrap_or_else(|_| "[]".to_string());
+    let redactions_json =
+        serde_json::to_string(&provenance.redactions).unwrap_or_else(|_| "[]".to_string());
+    let warnings_json =
+        serde_json::to_string(&provenance.warnings).unwrap_or_else(|_| "[]".to_string());
+
+    conn.execute(
+        "INSERT INTO draft_provenance (
+            ticket_keys, template_id, provider, model, options, system_prompt,
+            user_prompt, raw_output, structured, prompt_eval_count, eval_count, duration_ms,
+            article_references, redactions, warnings
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            keys_json,
+            provenance.template_id,
+            provenance.provider,
+            provenance.model,
+            options_json,
+            provenance.system_prompt,
+            provenance.user_prompt,
+            provenance.raw_output,
+            provenance.structured,
+            provenance.prompt_eval_count,
+            provenance.eval_count,
+            provenance.duration_ms,
+            references_json,
+            redactions_json,
+            warnings_json,
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Attach a provenance record to the article its draft was saved as
+pub fn link_article(conn: &Connection, id: i64, article_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "UPDATE draft_provenance SET article_id = ?1 WHERE id = ?2",
+        params![article_id, id],
+    )?;
+    Ok(())
+}
+
+/// Replace the redaction mapping of a record, e.g. once a regenerated
+/// section has added placeholders to it
+pub fn update_redactions(conn: &Connection, id: i64, redactions: &[Redaction]) -> SqliteResult<()> {
+    let redactions_json = serde_json::to_string(redactions).unwrap_or_else(|_| "[]".to_string());
+    conn.execute(
+        "UPDATE draft_provenance SET redactions = ?1 WHERE id = ?2",
+        params![redactions_json, id],
+    )?;
+    Ok(())
+}
+
+pub fn delete_for_article(conn: &Connection, article_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM draft_provenance WHERE article_id = ?1",
+        [article_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_provenance(conn: &Connection, id: i64) -> SqliteResult<DraftProvenance> {
+    let mut stmt = conn.prepare(
+        "SELECT id, article_id, ticket_keys, template_id, provider, model, options,
+                system_prompt, user_prompt, raw_output, structured, prompt_eval_count,
+                eval_count, duration_ms, created_at, article_references, redactions,
+                warnings
+         FROM draft_provenance WHERE id = ?1",
+    )?;
+
+    stmt.query_row([id], row_to_provenance)
+}
+
+/// Every generation recorded for an article, newest first
+pub fn list_for_article(conn: &Connection, article_id: i64) -> SqliteResult<Vec<DraftProvenance>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, article_id, ticket_keys, template_id, provider, model, options,
+                system_prompt, user_prompt, raw_output, structured, prompt_eval_count,
+                eval_count, duration_ms, created_at, article_references, redactions,
+                warnings
+         FROM draft_provenance WHERE article_id = ?1 ORDER BY id DESC",
+    )?;
+
+    let records = stmt.query_map([article_id], row_to_provenance)?;
+    records.collect()
+}
+
+fn row_to_provenance(row: &Row) -> SqliteResult<DraftProvenance> {
+    Ok(DraftProvenance {
+        id: row.get(0)?,
+        article_id: row.get(1)?,
+        ticket_keys: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+        template_id: row.get(3)?,
+        provider: row.get(4)?,
+        model: row.get(5)?,
+        options: serde_json::from_str(&row.get::<_, String>(6)?).unwrap_or_default(),
+        system_prompt: row.get(7)?,
+        user_prompt: row.get(8)?,
+        raw_output: row.get(9)?,
+        structured: row.get::<_, i32>(10)? != 0,
+        prompt_eval_count: row.get(11)?,
+        eval_count: row.get(12)?,
+        duration_ms: row.get(13)?,
+        created_at: row.get(14)?,
+        references: serde_json::from_str(&row.get::<_, String>(15)?).unwrap_or_default(),
+        redactions: serde_json::from_str(&row.get::<_, String>(16)?).unwrap_or_default(),
+        warnings: serde_json::from_str(&row.get::<_, String>(17)?).unwrap_or_default(),
+    })
+}
@@ -0,0 +1,43 @@
+use crate::models::RefinementMessage;
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+pub fn insert_message(
+    conn: &Connection,
+    article_id: i64,
+    role: &str,
+    content: &str,
+) -> SqliteResult<i64> {
+    conn.execute(
+        "INSERT INTO refinement_messages (article_id, role, content) VALUES (?1, ?2, ?3)",
+        params![article_id, role, content],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn list_messages(conn: &Connection, article_id: i64) -> SqliteResult<Vec<RefinementMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, article_id, role, content, created_at
+         FROM refinement_messages WHERE article_id = ?1 ORDER BY id ASC",
+    )?;
+
+    let messages = stmt.query_map([article_id], |row| {
+        Ok(RefinementMessage {
+            id: row.get(0)?,
+            article_id: row.get(1)?,
+            role: row.get(2)?,
+            content: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })?;
+
+    messages.collect()
+}
+
+pub fn delete_messages(conn: &Connection, article_id: i64) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM refinement_messages WHERE article_id = ?1",
+        [article_id],
+    )?;
+    Ok(())
+}
@@ -1,10 +1,11 @@
-use crate::models::Template;
-use rusqlite::{Connection, Result as SqliteResult};
+use crate::models::{ArticleField, GenerationSettings, Template};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use std::collections::BTreeMap;
 
 pub fn list_templates(conn: &Connection) -> SqliteResult<Vec<Template>> {
     let mut stmt = conn.prepare(
         "SELECT id, name, slug, description, system_prompt, output_structure,
-                is_builtin, created_at
+                is_builtin, created_at, field_mapping, generation_settings, model
          FROM kb_templates ORDER BY is_builtin DESC, name ASC",
     )?;
 
@@ -18,6 +19,9 @@ pub fn list_templates(conn: &Connection) -> SqliteResult<Vec<Template>> {
             output_structure: row.get(5)?,
             is_builtin: row.get::<_, i32>(6)? != 0,
             created_at: row.get(7)?,
+            field_mapping: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_default(),
+            generation: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            model: row.get(10)?,
         })
     })?;
 
@@ -27,7 +31,7 @@ pub fn list_templates(conn: &Connection) -> SqliteResult<Vec<Template>> {
 pub fn get_template(conn: &Connection, id: &str) -> SqliteResult<Template> {
     let mut stmt = conn.prepare(
         "SELECT id, name, slug, description, system_prompt, output_structure,
-                is_builtin, created_at
+                is_builtin, created_at, field_mapping, generation_settings, model
          FROM kb_templates WHERE id = ?1",
     )?;
 
@@ -41,6 +45,37 @@ pub fn get_template(conn: &Connection, id: &str) -> SqliteResult<Template> {
             output_structure: row.get(5)?,
             is_builtin: row.get::<_, i32>(6)? != 0,
             created_at: row.get(7)?,
+            field_mapping: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_default(),
+            generation: serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default(),
+            model: row.get(10)?,
         })
     })
 }
+
+pub fn update_field_mapping(
+    conn: &Connection,
+    id: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+) -> SqliteResult<()> {
+    let mapping_json = serde_json::to_string(mapping).unwrap_or_else(|_| "{}".to_string());
+    conn.execute(
+        "UPDATE kb_templates SET field_mapping = ?1, updated_at = datetime('now') WHERE id = ?2",
+        params![mapping_json, id],
+    )?;
+    Ok(())
+}
+
+pub fn update_generation_settings(
+    conn: &Connection,
+    id: &str,
+    generation: &GenerationSettings,
+    model: Option<&str>,
+) -> SqliteResult<()> {
+    let settings_json = serde_json::to_string(generation).unwrap_or_else(|_| "{}".to_string());
+    conn.execute(
+        "UPDATE kb_templates SET generation_settings = ?1, model = ?2, updated_at = datetime('now')
+         WHERE id = ?3",
+        params![settings_json, model.filter(|m| !m.is_empty()), id],
+    )?;
+    Ok(())
+}
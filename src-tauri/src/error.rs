@@ -18,9 +18,15 @@ pub enum AppError {
     #[error("Ollama unavailable at {url}")]
     OllamaUnavailable { url: String },
 
+    #[error("LLM server unavailable at {url}")]
+    LlmUnavailable { url: String },
+
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
+    #[error("Draft cancelled")]
+    Cancelled,
+
     #[error("Conversion error: {0}")]
     Conversion(String),
 
@@ -47,7 +53,9 @@ impl AppError {
             Self::TokenMissing { .. } => "token_missing",
             Self::Database(_) => "database",
             Self::OllamaUnavailable { .. } => "ollama_unavailable",
+            Self::LlmUnavailable { .. } => "llm_unavailable",
             Self::Network(_) => "network",
+            Self::Cancelled => "cancelled",
             Self::Conversion(_) => "conversion",
             Self::Internal(_) => "internal",
         }
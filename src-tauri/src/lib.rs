@@ -4,6 +4,8 @@ mod error;
 mod models;
 mod services;
 
+use commands::batch::ActiveBatches;
+use commands::drafting::ActiveDrafts;
 use commands::jira::JiraSettings;
 use std::sync::Mutex;
 use tauri::Manager;
@@ -30,27 +32,66 @@ pub fn run() {
             // Initialize Jira settings
             app.manage(Mutex::new(JiraSettings::default()));
 
+            // Track in-flight drafts so they can be cancelled
+            app.manage(Mutex::new(ActiveDrafts::default()));
+
+            // Background batch drafting, resuming whatever was left unfinished
+            app.manage(Mutex::new(ActiveBatches::default()));
+            commands::resume_batches(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::ping,
             commands::save_draft,
+            commands::update_draft,
             commands::get_article,
             commands::list_articles,
             commands::delete_draft,
             commands::export_markdown,
             commands::score_quality,
+            commands::check_template_conformance,
+            commands::insert_missing_sections,
             commands::scan_sensitive_data,
+            commands::check_grounding,
+            commands::suggest_tags,
+            commands::find_duplicate_articles,
             commands::list_templates,
             commands::get_template,
+            commands::update_template_field_mapping,
+            commands::update_template_generation_settings,
+            commands::recommend_templates,
             commands::test_jira_connection,
             commands::save_jira_config,
             commands::fetch_jira_ticket,
+            commands::fetch_linked_tickets,
             commands::search_jira_tickets,
             commands::disconnect_jira,
             commands::get_jira_connection_status,
             commands::check_ollama_status,
+            commands::list_ollama_models,
+            commands::show_ollama_model,
+            commands::pull_ollama_model,
+            commands::save_llm_api_key,
             commands::draft_with_llm,
+            commands::draft_article,
+            commands::cancel_draft,
+            commands::regenerate_section,
+            commands::refine_draft,
+            commands::get_refinement_history,
+            commands::clear_refinement_history,
+            commands::get_draft_provenance,
+            commands::get_article_references,
+            commands::get_provenance_record,
+            commands::restore_redacted_values,
+            commands::translate_article,
+            commands::list_article_translations,
+            commands::list_languages,
+            commands::start_batch_draft,
+            commands::list_batches,
+            commands::get_batch_jobs,
+            commands::cancel_batch,
+            commands::retry_batch_failures,
             commands::test_confluence_connection,
             commands::save_confluence_config,
             commands::disconnect_confluence,
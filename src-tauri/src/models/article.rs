@@ -1,12 +1,18 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
+/// Language of articles that do not say otherwise
+pub const DEFAULT_LANGUAGE: &str = "en";
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/bindings/")]
 pub struct Article {
     pub id: i64,
+    /// The primary ticket, i.e. the first of `source_ticket_keys`
     #[ts(optional)]
     pub ticket_key: Option<String>,
+    /// Every ticket the article was drafted from
+    pub source_ticket_keys: Vec<String>,
     pub title: String,
     pub problem: String,
     pub solution: String,
@@ -29,6 +35,12 @@ pub struct Article {
     pub quality_score: Option<u8>,
     #[ts(optional)]
     pub template_id: Option<String>,
+    /// ISO 639-1 code of the language the article is written in
+    pub language: String,
+    /// The article this one is a translation of; translations of the same
+    /// article are siblings
+    #[ts(optional)]
+    pub translation_of: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -60,6 +72,8 @@ impl ArticleStatus {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewArticle {
     pub ticket_key: Option<String>,
+    #[serde(default)]
+    pub source_ticket_keys: Vec<String>,
     pub title: String,
     pub problem: String,
     pub solution: String,
@@ -69,4 +83,75 @@ pub struct NewArticle {
     pub tags: Vec<String>,
     pub content_markdown: String,
     pub template_id: Option<String>,
+    /// Provenance record of the generation this draft came from
+    #[serde(default)]
+    pub provenance_id: Option<i64>,
+    /// ISO 639-1 code; English when a new article leaves it out, unchanged
+    /// when an update does
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Only read when the article is created
+    #[serde(default)]
+    pub translation_of: Option<i64>,
+}
+
+impl NewArticle {
+    /// Source tickets with the primary ticket first. Older callers only set
+    /// `ticket_key`, newer ones may only set `source_ticket_keys`.
+    pub fn source_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.ticket_key.iter().cloned().collect();
+        for key in &self.source_ticket_keys {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
+}
+
+/// A language articles can be drafted or translated into
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Language {
+    /// ISO 639-1 code, e.g. "de"
+    pub code: String,
+    /// English name, as given to the model
+    pub name: String,
+}
+
+/// A label proposed for an article, best first
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TagSuggestion {
+    /// Already normalized to Confluence label syntax
+    pub tag: String,
+    pub score: f32,
+    /// Where the tag came from, in the order it was found
+    pub sources: Vec<TagSource>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum TagSource {
+    JiraLabel,
+    Component,
+    Keyword,
+    /// Already used by other articles in the knowledge base
+    Existing,
+}
+
+/// An existing article similar to the tickets about to be drafted
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DuplicateMatch {
+    pub article_id: i64,
+    pub title: String,
+    #[ts(optional)]
+    pub ticket_key: Option<String>,
+    pub status: ArticleStatus,
+    #[ts(optional)]
+    pub confluence_url: Option<String>,
+    /// Cosine similarity of the embeddings, between 0 and 1
+    pub similarity: f32,
 }
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// How requests to Jira or Confluence are authenticated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum AuthMode {
+    /// Probe the server and use whichever of the other two modes it accepts
+    #[default]
+    Auto,
+    /// Personal access token sent as a Bearer token (Server / Data Center)
+    Pat,
+    /// Account email and API token sent with Basic auth (Atlassian Cloud)
+    Basic,
+}
+
+/// A Jira or Confluence credential as stored in the keyring. Never sent to
+/// the frontend.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Credentials {
+    Pat { token: String },
+    Basic { email: String, token: String },
+}
+
+impl Credentials {
+    pub fn mode(&self) -> AuthMode {
+        match self {
+            Credentials::Pat { .. } => AuthMode::Pat,
+            Credentials::Basic { .. } => AuthMode::Basic,
+        }
+    }
+}
+
+// Keeps tokens out of logs
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credentials::Pat { .. } => f.write_str("Pat { .. }"),
+            Credentials::Basic { email, .. } => write!(f, "Basic {{ email: {:?}, .. }}", email),
+        }
+    }
+}
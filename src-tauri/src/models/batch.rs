@@ -0,0 +1,130 @@
+use crate::models::{FlaggedSection, LlmProviderKind};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// A JQL search being drafted in the background, one job per ticket
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BatchRun {
+    pub id: i64,
+    pub jql: String,
+    pub template_id: String,
+    /// Jira instance the tickets are fetched from, kept so the batch can
+    /// resume after a restart
+    pub jira_url: String,
+    pub provider: LlmProviderKind,
+    pub llm_url: String,
+    pub model: String,
+    #[ts(optional)]
+    pub context_length: Option<usize>,
+    /// Tickets drafted at the same time
+    pub concurrency: usize,
+    /// Attempts per ticket before it is marked failed
+    pub max_attempts: u32,
+    pub status: BatchStatus,
+    pub total_jobs: usize,
+    pub completed_jobs: usize,
+    pub failed_jobs: usize,
+    pub created_at: String,
+}
+
+/// Settings of a batch as it is queued
+#[derive(Debug, Clone)]
+pub struct NewBatchRun {
+    pub jql: String,
+    pub template_id: String,
+    pub jira_url: String,
+    pub provider: LlmProviderKind,
+    pub llm_url: String,
+    pub model: String,
+    pub context_length: Option<usize>,
+    pub concurrency: usize,
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum BatchStatus {
+    Running,
+    Finished,
+    Cancelled,
+}
+
+impl BatchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchStatus::Running => "running",
+            BatchStatus::Finished => "finished",
+            BatchStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "running" => Ok(BatchStatus::Running),
+            "finished" => Ok(BatchStatus::Finished),
+            "cancelled" => Ok(BatchStatus::Cancelled),
+            _ => Err(format!("Invalid batch status: {}", s)),
+        }
+    }
+}
+
+/// Drafting of one ticket of a batch. Also the payload of the
+/// `batch-job-status` event emitted on every status change.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BatchJob {
+    pub id: i64,
+    pub batch_id: i64,
+    pub ticket_key: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    #[ts(optional)]
+    pub last_error: Option<String>,
+    /// The saved draft, once the job is done
+    #[ts(optional)]
+    pub article_id: Option<i64>,
+    /// When a failed attempt will be retried
+    #[ts(optional)]
+    pub next_attempt_at: Option<String>,
+    /// Problems found in the ticket while drafting it, as on `ArticleDraft`
+    pub warnings: Vec<String>,
+    /// Details of the draft the ticket does not back up, as on `ArticleDraft`
+    pub ungrounded: Vec<FlaggedSection>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "done" => Ok(JobStatus::Done),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(format!("Invalid job status: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::models::{FlaggedSection, GenerationSettings, Redaction};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Payload of the `draft-progress` event emitted while a draft is streaming
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DraftProgress {
+    pub draft_id: String,
+    pub delta: String,
+    pub chars_generated: usize,
+}
+
+/// One turn of the refinement conversation kept for an article
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct RefinementMessage {
+    pub id: i64,
+    pub article_id: i64,
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// A drafted article split into the fields of `NewArticle`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ArticleDraft {
+    pub title: String,
+    pub problem: String,
+    pub solution: String,
+    #[ts(optional)]
+    pub expected_result: Option<String>,
+    #[ts(optional)]
+    pub prerequisites: Option<String>,
+    #[ts(optional)]
+    pub additional_notes: Option<String>,
+    pub tags: Vec<String>,
+    pub content_markdown: String,
+    /// True when the model answered with structured output; false when the
+    /// fields were parsed back out of markdown
+    pub structured: bool,
+    /// Commands, paths, versions and similar details in the draft that do
+    /// not appear in any source ticket
+    #[serde(default)]
+    pub ungrounded: Vec<FlaggedSection>,
+    /// Id of the stored `DraftProvenance`; pass it back in `NewArticle` when
+    /// saving so the record gets linked to the article
+    #[serde(default)]
+    #[ts(optional)]
+    pub provenance_id: Option<i64>,
+    /// Existing articles given to the model as reference material
+    #[serde(default)]
+    pub references: Vec<ArticleReference>,
+    /// Sensitive values in the tickets that the model only saw as
+    /// placeholders; `sensitive_data::restore` puts them back
+    #[serde(default)]
+    pub redactions: Vec<Redaction>,
+    /// Problems with the source tickets to review before publishing, such as
+    /// text that tries to give the model instructions
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// ISO 639-1 code when the draft was asked for in another language than
+    /// the default
+    #[serde(default)]
+    #[ts(optional)]
+    pub language: Option<String>,
+}
+
+/// An existing KB article retrieved as reference material for a draft
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ArticleReference {
+    pub article_id: i64,
+    pub title: String,
+    /// Start of the article's problem statement
+    pub summary: String,
+    #[ts(optional)]
+    pub ticket_key: Option<String>,
+    #[ts(optional)]
+    pub confluence_url: Option<String>,
+    /// Relevance to the drafted tickets; only comparable within one draft
+    pub score: f32,
+}
+
+/// What went into and came out of one draft generation, so reviewers can
+/// trace an article back to the prompt that produced it
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DraftProvenance {
+    pub id: i64,
+    /// Unset until the draft is saved as an article
+    #[ts(optional)]
+    pub article_id: Option<i64>,
+    pub ticket_keys: Vec<String>,
+    #[ts(optional)]
+    pub template_id: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub options: GenerationSettings,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    /// Existing articles included in the prompt
+    pub references: Vec<ArticleReference>,
+    /// Placeholders used in the prompt and the values they stand for
+    pub redactions: Vec<Redaction>,
+    /// Problems found in the source tickets, as in `ArticleDraft::warnings`
+    pub warnings: Vec<String>,
+    /// Model output before `post_process`
+    pub raw_output: String,
+    pub structured: bool,
+    #[ts(optional)]
+    pub prompt_eval_count: Option<u64>,
+    #[ts(optional)]
+    pub eval_count: Option<u64>,
+    /// Time from sending the prompt to the last token
+    pub duration_ms: u64,
+    pub created_at: String,
+}
+
+/// A provenance record as produced by `drafter::draft`, before it is stored
+#[derive(Debug, Clone)]
+pub struct NewDraftProvenance {
+    pub ticket_keys: Vec<String>,
+    pub template_id: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub options: GenerationSettings,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub references: Vec<ArticleReference>,
+    pub redactions: Vec<Redaction>,
+    pub warnings: Vec<String>,
+    pub raw_output: String,
+    pub structured: bool,
+    pub prompt_eval_count: Option<u64>,
+    pub eval_count: Option<u64>,
+    pub duration_ms: u64,
+}
@@ -16,6 +16,14 @@ pub struct JiraTicket {
     pub labels: Vec<String>,
     pub components: Vec<String>,
     pub comments: Vec<JiraComment>,
+    /// Issues linked from this ticket (duplicates, relates to, ...)
+    #[serde(default)]
+    pub linked_issues: Vec<JiraIssueLink>,
+    /// Creation, status, assignee, priority and resolution changes and
+    /// comments, oldest first. Changes are only known for tickets fetched
+    /// one by one, not for search results.
+    #[serde(default)]
+    pub timeline: Vec<TimelineEvent>,
     pub created: String,
     pub updated: String,
 }
@@ -27,3 +35,40 @@ pub struct JiraComment {
     pub body: String,
     pub created: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct JiraIssueLink {
+    pub key: String,
+    /// How this ticket relates to the linked one, e.g. "is duplicated by"
+    pub relationship: String,
+    pub summary: String,
+}
+
+/// One entry of a ticket's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TimelineEvent {
+    /// As reported by Jira, e.g. "2024-01-01T10:00:00.000+0000"
+    pub timestamp: String,
+    pub author: String,
+    pub kind: TimelineEventKind,
+    /// Value before the change
+    #[ts(optional)]
+    pub from: Option<String>,
+    /// Value after the change, or the text of a comment
+    #[ts(optional)]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum TimelineEventKind {
+    Created,
+    Status,
+    Assignee,
+    Priority,
+    Resolution,
+    Comment,
+}
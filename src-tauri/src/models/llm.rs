@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Which API the configured LLM server speaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum LlmProviderKind {
+    /// Ollama's native API
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions`, as served by llama.cpp and vLLM
+    OpenaiCompatible,
+}
+
+impl LlmProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmProviderKind::Ollama => "ollama",
+            LlmProviderKind::OpenaiCompatible => "openai_compatible",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "ollama" => Ok(LlmProviderKind::Ollama),
+            "openai_compatible" => Ok(LlmProviderKind::OpenaiCompatible),
+            _ => Err(format!("Invalid LLM provider: {}", s)),
+        }
+    }
+}
+
+/// A model installed on the Ollama server, as listed by `/api/tags`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct OllamaModel {
+    pub name: String,
+    /// Size on disk in bytes
+    pub size: u64,
+    pub modified_at: String,
+    #[ts(optional)]
+    pub parameter_size: Option<String>,
+    #[ts(optional)]
+    pub quantization_level: Option<String>,
+}
+
+/// Details of one model, as reported by `/api/show`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct OllamaModelDetails {
+    pub name: String,
+    #[ts(optional)]
+    pub family: Option<String>,
+    #[ts(optional)]
+    pub parameter_size: Option<String>,
+    #[ts(optional)]
+    pub quantization_level: Option<String>,
+    /// Longest context the model was trained for. Useful as an upper bound
+    /// for the configured context length.
+    #[ts(optional)]
+    pub context_length: Option<u64>,
+}
+
+/// Payload of the `model-pull-progress` event emitted while a model downloads
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ModelPullProgress {
+    pub model: String,
+    /// Ollama's status line, e.g. "pulling manifest" or "success"
+    pub status: String,
+    /// Bytes downloaded of the current layer
+    #[ts(optional)]
+    pub completed: Option<u64>,
+    /// Size of the current layer in bytes
+    #[ts(optional)]
+    pub total: Option<u64>,
+}
@@ -1,11 +1,25 @@
 pub mod article;
+pub mod auth;
+pub mod batch;
 pub mod confluence;
+pub mod drafting;
 pub mod jira;
+pub mod llm;
 pub mod quality;
 pub mod template;
 
-pub use article::{Article, ArticleStatus, NewArticle};
+pub use article::{
+    Article, ArticleStatus, DuplicateMatch, Language, NewArticle, TagSource, TagSuggestion,
+    DEFAULT_LANGUAGE,
+};
+pub use auth::{AuthMode, Credentials};
+pub use batch::{BatchJob, BatchRun, BatchStatus, JobStatus, NewBatchRun};
 pub use confluence::{ConfluenceSpace, ConversionResult, PublishResult};
-pub use jira::{JiraComment, JiraTicket};
-pub use quality::{FlaggedSection, QualityScore};
-pub use template::Template;
+pub use drafting::{
+    ArticleDraft, ArticleReference, DraftProgress, DraftProvenance, NewDraftProvenance,
+    RefinementMessage,
+};
+pub use jira::{JiraComment, JiraIssueLink, JiraTicket, TimelineEvent, TimelineEventKind};
+pub use llm::{LlmProviderKind, ModelPullProgress, OllamaModel, OllamaModelDetails};
+pub use quality::{ConformanceReport, FlaggedSection, QualityScore, Redaction, RenamedSection};
+pub use template::{ArticleField, GenerationSettings, Template, TemplateRecommendation};
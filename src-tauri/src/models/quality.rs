@@ -25,3 +25,35 @@ pub struct FlaggedSection {
     pub start_col: usize,
     pub end_col: usize,
 }
+
+/// A sensitive value replaced by a placeholder before ticket content is sent
+/// to the LLM. Kept locally so the real value can be put back in the draft.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Redaction {
+    /// What the model saw instead, e.g. `<IP_1>`
+    pub placeholder: String,
+    pub pattern_type: String,
+    pub value: String,
+}
+
+/// How a draft's sections compare with its template's `output_structure`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ConformanceReport {
+    /// Template sections the draft does not have
+    pub missing: Vec<String>,
+    /// Draft sections that match no template section
+    pub extra: Vec<String>,
+    /// Template sections the draft has in a different place
+    pub out_of_order: Vec<String>,
+    /// Template sections the draft has under another name
+    pub renamed: Vec<RenamedSection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct RenamedSection {
+    pub expected: String,
+    pub found: String,
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use ts_rs::TS;
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -10,6 +11,62 @@ pub struct Template {
     pub description: String,
     pub system_prompt: String,
     pub output_structure: String,
+    /// Which article field each `output_structure` heading fills. Headings
+    /// not listed fall back to matching common names like "Resolution".
+    #[serde(default)]
+    pub field_mapping: BTreeMap<String, ArticleField>,
+    /// Sampling settings used when drafting with this template
+    #[serde(default)]
+    pub generation: GenerationSettings,
+    /// Model to draft with instead of the one configured in the settings
+    #[ts(optional)]
+    pub model: Option<String>,
     pub is_builtin: bool,
     pub created_at: String,
 }
+
+/// Model parameters a template drafts with. Unset values keep the backend's
+/// defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+#[serde(default)]
+pub struct GenerationSettings {
+    #[ts(optional)]
+    pub temperature: Option<f32>,
+    #[ts(optional)]
+    pub top_p: Option<f32>,
+    /// Context window in tokens; takes precedence over the configured one
+    #[ts(optional)]
+    pub num_ctx: Option<usize>,
+    /// Fixed seed, for reproducible output
+    #[ts(optional)]
+    pub seed: Option<i64>,
+    /// Maximum number of tokens to generate
+    #[ts(optional)]
+    pub num_predict: Option<usize>,
+    /// Sequences that end generation when produced
+    pub stop: Vec<String>,
+}
+
+/// The `NewArticle` field an article section is stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+#[serde(rename_all = "snake_case")]
+pub enum ArticleField {
+    Problem,
+    Solution,
+    ExpectedResult,
+    Prerequisites,
+    AdditionalNotes,
+}
+
+/// How well a template suits a ticket, as suggested by `recommend_templates`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TemplateRecommendation {
+    pub template_id: String,
+    pub template_name: String,
+    /// Between 0 and 1; the confidences of one recommendation add up to 1
+    pub confidence: f32,
+    pub reason: String,
+}
@@ -0,0 +1,402 @@
+use chrono::DateTime;
+use serde_json::Value;
+
+/// A Jira text field as markdown: plain strings (REST API v2) are returned
+/// as they are, Atlassian Document Format objects (v3 and Cloud) are
+/// converted. `None` for anything else, such as a missing field.
+pub fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(_) => Some(convert(value)),
+        _ => None,
+    }
+}
+
+/// Convert an ADF document to markdown. Nodes without a markdown equivalent
+/// keep their text; attachments become placeholders.
+pub fn convert(doc: &Value) -> String {
+    blocks(children(doc), "\n\n")
+}
+
+fn children(node: &Value) -> &[Value] {
+    node["content"].as_array().map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn attr<'a>(node: &'a Value, name: &str) -> Option<&'a str> {
+    node["attrs"][name].as_str().filter(|s| !s.is_empty())
+}
+
+fn blocks(nodes: &[Value], separator: &str) -> String {
+    nodes
+        .iter()
+        .map(block)
+        .filter(|b| !b.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn block(node: &Value) -> String {
+    match node["type"].as_str().unwrap_or("") {
+        "paragraph" => inline(children(node)),
+        "heading" => {
+            let level = node["attrs"]["level"].as_u64().unwrap_or(1).clamp(1, 6) as usize;
+            format!("{} {}", "#".repeat(level), inline(children(node)))
+        }
+        "bulletList" => list(node, |_| "- ".to_string()),
+        "orderedList" => {
+            let start = node["attrs"]["order"].as_u64().unwrap_or(1);
+            list(node, |i| format!("{}. ", start + i as u64))
+        }
+        "taskList" => children(node)
+            .iter()
+            .map(|item| {
+                let done = attr(item, "state") == Some("DONE");
+                let marker = if done { "- [x] " } else { "- [ ] " };
+                indent_item(marker, &item_body(item))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "decisionList" => children(node)
+            .iter()
+            .map(|item| indent_item("- Decision: ", &item_body(item)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "codeBlock" => {
+            let code: String = children(node)
+                .iter()
+                .filter_map(|t| t["text"].as_str())
+                .collect();
+            format!(
+                "```{}\n{}\n```",
+                attr(node, "language").unwrap_or(""),
+                code.trim_end_matches('\n')
+            )
+        }
+        "blockquote" => quote(&blocks(children(node), "\n\n")),
+        "panel" => {
+            let label = match attr(node, "panelType").unwrap_or("info") {
+                "note" => "Note",
+                "warning" => "Warning",
+                "error" => "Error",
+                "success" => "Success",
+                "tip" => "Tip",
+                _ => "Info",
+            };
+            quote(&format!(
+                "**{}:** {}",
+                label,
+                blocks(children(node), "\n\n")
+            ))
+        }
+        "rule" => "---".to_string(),
+        "table" => table(node),
+        "expand" | "nestedExpand" => {
+            let body = blocks(children(node), "\n\n");
+            match attr(node, "title") {
+                Some(title) => format!("**{}**\n\n{}", title, body),
+                None => body,
+            }
+        }
+        "mediaSingle" | "mediaGroup" => children(node)
+            .iter()
+            .map(media)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "media" => media(node),
+        "blockCard" | "embedCard" => attr(node, "url")
+            .map(|url| format!("<{}>", url))
+            .unwrap_or_default(),
+        // Unknown blocks and inline nodes at the top level: keep their text
+        _ if children(node).iter().any(is_block) => blocks(children(node), "\n\n"),
+        _ => inline(std::slice::from_ref(node)),
+    }
+}
+
+fn is_block(node: &Value) -> bool {
+    !matches!(
+        node["type"].as_str().unwrap_or(""),
+        "text"
+            | "hardBreak"
+            | "mention"
+            | "emoji"
+            | "inlineCard"
+            | "date"
+            | "status"
+            | "mediaInline"
+            | "placeholder"
+    )
+}
+
+fn list(node: &Value, marker: impl Fn(usize) -> String) -> String {
+    children(node)
+        .iter()
+        .enumerate()
+        .map(|(i, item)| indent_item(&marker(i), &item_body(item)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A list item's content. Task and decision items hold inline nodes,
+/// list items hold paragraphs and nested lists, which are kept tight.
+fn item_body(item: &Value) -> String {
+    if children(item).iter().any(is_block) {
+        blocks(children(item), "\n")
+    } else {
+        inline(children(item))
+    }
+}
+
+/// Put `marker` before the first line and indent the rest to match
+fn indent_item(marker: &str, body: &str) -> String {
+    let indent = " ".repeat(marker.len());
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| match (i, line.is_empty()) {
+            (0, _) => format!("{}{}", marker, line),
+            (_, true) => String::new(),
+            _ => format!("{}{}", indent, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn quote(body: &str) -> String {
+    body.lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A GFM table. The first row is the header, as GFM requires one; cells
+/// with several paragraphs are joined with `<br>`.
+fn table(node: &Value) -> String {
+    let rows: Vec<Vec<String>> = children(node)
+        .iter()
+        .map(|row| {
+            children(row)
+                .iter()
+                .map(|cell| {
+                    blocks(children(cell), "\n")
+                        .replace('|', "\\|")
+                        .lines()
+                        .collect::<Vec<_>>()
+                        .join("<br>")
+                })
+                .collect()
+        })
+        .collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let line = |cells: &[String]| {
+        let mut cells = cells.to_vec();
+        cells.resize(columns, String::new());
+        format!("| {} |", cells.join(" | "))
+    };
+    let mut lines = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+fn media(node: &Value) -> String {
+    match attr(node, "alt") {
+        Some(alt) => format!("[Attachment: {}]", alt),
+        None => "[Attachment]".to_string(),
+    }
+}
+
+fn inline(nodes: &[Value]) -> String {
+    nodes.iter().map(inline_node).collect()
+}
+
+fn inline_node(node: &Value) -> String {
+    match node["type"].as_str().unwrap_or("") {
+        "text" => marked_text(node),
+        "hardBreak" => "\n".to_string(),
+        "mention" => {
+            let name = attr(node, "text").unwrap_or("someone");
+            if name.starts_with('@') {
+                name.to_string()
+            } else {
+                format!("@{}", name)
+            }
+        }
+        "emoji" => attr(node, "text")
+            .or_else(|| attr(node, "shortName"))
+            .unwrap_or("")
+            .to_string(),
+        "inlineCard" => attr(node, "url")
+            .map(|url| format!("<{}>", url))
+            .unwrap_or_default(),
+        "date" => attr(node, "timestamp")
+            .and_then(|ms| ms.parse::<i64>().ok())
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        "status" => attr(node, "text")
+            .map(|s| format!("[{}]", s.to_uppercase()))
+            .unwrap_or_default(),
+        "mediaInline" => media(node),
+        "placeholder" => String::new(),
+        _ => inline(children(node)),
+    }
+}
+
+/// Text with its marks as markdown emphasis, code and links
+fn marked_text(node: &Value) -> String {
+    let mut text = node["text"].as_str().unwrap_or("").to_string();
+    let marks = node["marks"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let has = |mark: &str| marks.iter().any(|m| m["type"] == mark);
+
+    if has("code") {
+        text = format!("`{}`", text);
+    } else if !text.trim().is_empty() {
+        // Emphasis markers must touch the text, so keep surrounding spaces outside
+        let start = text.len() - text.trim_start().len();
+        let end = text.trim_end().len();
+        let (lead, core, trail) = (&text[..start], &text[start..end], &text[end..]);
+        let mut core = core.to_string();
+        for (mark, delimiter) in [("strike", "~~"), ("em", "*"), ("strong", "**")] {
+            if has(mark) {
+                core = format!("{}{}{}", delimiter, core, delimiter);
+            }
+        }
+        text = format!("{}{}{}", lead, core, trail);
+    }
+
+    match marks.iter().find(|m| m["type"] == "link") {
+        Some(link) => match link["attrs"]["href"].as_str() {
+            Some(href) => format!("[{}]({})", text, href),
+            None => text,
+        },
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc(content: Value) -> Value {
+        json!({ "type": "doc", "version": 1, "content": content })
+    }
+
+    fn para(text: &str) -> Value {
+        json!({ "type": "paragraph", "content": [{ "type": "text", "text": text }] })
+    }
+
+    #[test]
+    fn test_text_accepts_strings_and_documents() {
+        assert_eq!(
+            text(&json!("Plain *wiki* text")).as_deref(),
+            Some("Plain *wiki* text")
+        );
+        assert_eq!(
+            text(&doc(json!([para("From ADF")]))).as_deref(),
+            Some("From ADF")
+        );
+        assert_eq!(text(&Value::Null), None);
+    }
+
+    #[test]
+    fn test_paragraphs_headings_and_marks() {
+        let adf = doc(json!([
+            { "type": "heading", "attrs": { "level": 2 }, "content": [{ "type": "text", "text": "Steps" }] },
+            { "type": "paragraph", "content": [
+                { "type": "text", "text": "Run " },
+                { "type": "text", "text": "ipconfig /flushdns", "marks": [{ "type": "code" }] },
+                { "type": "text", "text": " as " },
+                { "type": "text", "text": "admin ", "marks": [{ "type": "strong" }] },
+                { "type": "text", "text": "first", "marks": [{ "type": "em" }] },
+                { "type": "hardBreak" },
+                { "type": "text", "text": "See the guide", "marks": [{ "type": "link", "attrs": { "href": "https://kb.example.com/dns" } }] },
+                { "type": "text", "text": ", ask " },
+                { "type": "mention", "attrs": { "id": "123", "text": "@Dana Lee" } },
+                { "type": "text", "text": " or check " },
+                { "type": "status", "attrs": { "text": "in progress", "color": "blue" } }
+            ]}
+        ]));
+
+        assert_eq!(
+            convert(&adf),
+            "## Steps\n\nRun `ipconfig /flushdns` as **admin** *first*\n\
+             [See the guide](https://kb.example.com/dns), ask @Dana Lee or check [IN PROGRESS]"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let adf = doc(json!([
+            { "type": "orderedList", "attrs": { "order": 3 }, "content": [
+                { "type": "listItem", "content": [
+                    para("Open settings"),
+                    { "type": "bulletList", "content": [
+                        { "type": "listItem", "content": [para("Network")] },
+                        { "type": "listItem", "content": [para("VPN")] }
+                    ]}
+                ]},
+                { "type": "listItem", "content": [para("Reconnect")] }
+            ]},
+            { "type": "taskList", "content": [
+                { "type": "taskItem", "attrs": { "state": "DONE" }, "content": [{ "type": "text", "text": "Reboot" }] },
+                { "type": "taskItem", "attrs": { "state": "TODO" }, "content": [{ "type": "text", "text": "Patch" }] }
+            ]}
+        ]));
+
+        assert_eq!(
+            convert(&adf),
+            "3. Open settings\n   - Network\n   - VPN\n4. Reconnect\n\n- [x] Reboot\n- [ ] Patch"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_panels_and_quotes() {
+        let adf = doc(json!([
+            { "type": "codeBlock", "attrs": { "language": "powershell" }, "content": [
+                { "type": "text", "text": "Get-Service\nRestart-Service Spooler\n" }
+            ]},
+            { "type": "panel", "attrs": { "panelType": "warning" }, "content": [
+                para("Back up the registry first."),
+                para("It cannot be undone.")
+            ]},
+            { "type": "blockquote", "content": [para("Customer quote")] },
+            { "type": "rule" },
+            { "type": "mediaSingle", "content": [
+                { "type": "media", "attrs": { "id": "abc", "type": "file", "alt": "error.png" } }
+            ]}
+        ]));
+
+        assert_eq!(
+            convert(&adf),
+            "```powershell\nGet-Service\nRestart-Service Spooler\n```\n\n\
+             > **Warning:** Back up the registry first.\n>\n> It cannot be undone.\n\n\
+             > Customer quote\n\n---\n\n[Attachment: error.png]"
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        let cell = |kind: &str, text: &str| json!({ "type": kind, "content": [para(text)] });
+        let adf = doc(json!([
+            { "type": "table", "content": [
+                { "type": "tableRow", "content": [cell("tableHeader", "Host"), cell("tableHeader", "Status")] },
+                { "type": "tableRow", "content": [cell("tableCell", "mail-01"), cell("tableCell", "up | degraded")] },
+                { "type": "tableRow", "content": [cell("tableCell", "mail-02")] }
+            ]}
+        ]));
+
+        assert_eq!(
+            convert(&adf),
+            "| Host | Status |\n| --- | --- |\n| mail-01 | up \\| degraded |\n| mail-02 |  |"
+        );
+    }
+}
@@ -0,0 +1,582 @@
+use crate::models::{ArticleDraft, ArticleField, NewArticle, Template};
+use crate::services::sections;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Body written for template sections the model left empty
+pub const MISSING_SECTION: &str = "[Not available in ticket - please add]";
+
+/// Common section names by field, for headings a template does not map
+const HEADING_FIELDS: &[(&str, ArticleField)] = &[
+    ("problem", ArticleField::Problem),
+    ("symptoms", ArticleField::Problem),
+    ("overview", ArticleField::Problem),
+    ("question", ArticleField::Problem),
+    ("resolution", ArticleField::Solution),
+    ("solution", ArticleField::Solution),
+    ("steps", ArticleField::Solution),
+    ("workaround", ArticleField::Solution),
+    ("answer", ArticleField::Solution),
+    ("expected result", ArticleField::ExpectedResult),
+    ("verification", ArticleField::ExpectedResult),
+    ("prerequisites", ArticleField::Prerequisites),
+    ("environment", ArticleField::Prerequisites),
+    ("affected systems", ArticleField::Prerequisites),
+    ("requirements", ArticleField::Prerequisites),
+    ("additional notes", ArticleField::AdditionalNotes),
+    ("notes", ArticleField::AdditionalNotes),
+    ("details", ArticleField::AdditionalNotes),
+];
+
+/// The article fields, before they are attached to a ticket and template
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArticleFields {
+    pub title: String,
+    pub problem: String,
+    pub solution: String,
+    pub expected_result: Option<String>,
+    pub prerequisites: Option<String>,
+    pub additional_notes: Option<String>,
+}
+
+/// A heading of the template's `output_structure`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateSection {
+    pub heading: String,
+    pub level: u8,
+}
+
+/// The sections an article drafted from `output_structure` should have,
+/// in order. The level-1 title heading is not a section.
+pub fn template_sections(output_structure: &str) -> Vec<TemplateSection> {
+    sections::parse_sections(output_structure)
+        .into_iter()
+        .filter(|s| s.level > 1)
+        .map(|s| TemplateSection {
+            heading: s.heading,
+            level: s.level,
+        })
+        .collect()
+}
+
+/// The field a heading maps to: the template's own mapping first, then
+/// common section names
+pub fn field_for_heading(
+    heading: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+) -> Option<ArticleField> {
+    let heading = heading.trim();
+    mapping
+        .iter()
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(heading))
+        .map(|(_, field)| *field)
+        .or_else(|| default_field(heading))
+}
+
+fn default_field(heading: &str) -> Option<ArticleField> {
+    let heading = heading.to_lowercase();
+    HEADING_FIELDS
+        .iter()
+        .find(|(name, _)| heading == *name)
+        .or_else(|| {
+            HEADING_FIELDS
+                .iter()
+                .find(|(name, _)| heading.contains(name))
+        })
+        .map(|(_, field)| *field)
+}
+
+/// JSON schema for structured drafting: a title, one string per template
+/// section keyed by its heading, and tags
+pub fn output_schema(sections: &[TemplateSection]) -> Value {
+    let properties: serde_json::Map<String, Value> = sections
+        .iter()
+        .map(|s| (s.heading.clone(), json!({ "type": "string" })))
+        .collect();
+    let required: Vec<&str> = sections.iter().map(|s| s.heading.as_str()).collect();
+
+    json!({
+        "type": "object",
+        "properties": {
+            "title": { "type": "string" },
+            "sections": {
+                "type": "object",
+                "properties": properties,
+                "required": required
+            },
+            "tags": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["title", "sections", "tags"]
+    })
+}
+
+/// Build a draft from the model's JSON answer, rendering `content_markdown`
+/// through the template's `output_structure`. Returns `None` when the answer
+/// is not the JSON object asked for.
+pub fn draft_from_json(raw: &str, template: &Template) -> Option<ArticleDraft> {
+    let value: Value = serde_json::from_str(strip_code_fence(raw)).ok()?;
+    let title = value["title"].as_str()?.trim();
+    let section_map = value["sections"].as_object()?;
+    if title.is_empty() {
+        return None;
+    }
+
+    let template_sections = template_sections(&template.output_structure);
+    let mut bodies: Vec<(TemplateSection, String)> = Vec::new();
+
+    for section in &template_sections {
+        let body = section_map
+            .iter()
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(&section.heading))
+            .and_then(|(_, value)| value.as_str())
+            .unwrap_or("");
+        bodies.push((section.clone(), body.trim().to_string()));
+    }
+
+    // Keep anything the model added beyond the template rather than lose it
+    for (key, value) in section_map {
+        let known = template_sections
+            .iter()
+            .any(|s| key.trim().eq_ignore_ascii_case(&s.heading));
+        if let (false, Some(body)) = (known, value.as_str()) {
+            if !body.trim().is_empty() {
+                let level = template_sections.first().map(|s| s.level).unwrap_or(2);
+                bodies.push((
+                    TemplateSection {
+                        heading: key.trim().to_string(),
+                        level,
+                    },
+                    body.trim().to_string(),
+                ));
+            }
+        }
+    }
+
+    let title = render_title(&template.output_structure, title);
+    let markdown = render_markdown(&title, &bodies);
+
+    let tags = value["tags"]
+        .as_array()
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|t| t.as_str())
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let named: Vec<(&str, &str)> = bodies
+        .iter()
+        .map(|(s, body)| (s.heading.as_str(), body.as_str()))
+        .collect();
+    let fields = fields_from_sections(&title, &named, &template.field_mapping);
+
+    Some(into_draft(fields, tags, markdown, true))
+}
+
+/// Build a draft from markdown the model wrote freely, filling the fields
+/// from its sections
+pub fn draft_from_markdown(markdown: String, template: &Template) -> ArticleDraft {
+    let fields = fields_from_markdown(&markdown, &template.field_mapping);
+    into_draft(fields, Vec::new(), markdown, false)
+}
+
+/// Re-derive the article fields from `content_markdown`, so hand edits to
+/// the markdown are not left behind in stale columns. Markdown without any
+/// sections is left alone: there is nothing to derive the fields from.
+pub fn sync_fields(article: &mut NewArticle, template: Option<&Template>) {
+    let empty = BTreeMap::new();
+    let mapping = template.map(|t| &t.field_mapping).unwrap_or(&empty);
+
+    let has_sections = sections::parse_sections(&article.content_markdown)
+        .iter()
+        .any(|s| s.level > 1);
+    if !has_sections {
+        return;
+    }
+
+    let fields = fields_from_markdown(&article.content_markdown, mapping);
+    if !fields.title.is_empty() {
+        article.title = fields.title;
+    }
+    article.problem = fields.problem;
+    article.solution = fields.solution;
+    article.expected_result = fields.expected_result;
+    article.prerequisites = fields.prerequisites;
+    article.additional_notes = fields.additional_notes;
+}
+
+/// Split an article's markdown into fields: the level-1 heading is the
+/// title, and every other top-level section goes to the field its heading
+/// maps to. Sections that map to nothing end up in the additional notes,
+/// under their own heading.
+pub fn fields_from_markdown(
+    markdown: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+) -> ArticleFields {
+    let all = sections::parse_sections(markdown);
+    let title = all
+        .iter()
+        .find(|s| s.level == 1)
+        .map(|s| s.heading.clone())
+        .unwrap_or_default();
+
+    // Subsections stay inside their parent's body
+    let top_level = all
+        .iter()
+        .filter(|s| s.level > 1)
+        .map(|s| s.level)
+        .min()
+        .unwrap_or(2);
+    let named: Vec<(&str, &str)> = all
+        .iter()
+        .filter(|s| s.level == top_level)
+        .map(|s| (s.heading.as_str(), s.body(markdown).trim()))
+        .collect();
+
+    fields_from_sections(&title, &named, mapping)
+}
+
+fn fields_from_sections(
+    title: &str,
+    named: &[(&str, &str)],
+    mapping: &BTreeMap<String, ArticleField>,
+) -> ArticleFields {
+    let mut problem = Vec::new();
+    let mut solution = Vec::new();
+    let mut expected_result = Vec::new();
+    let mut prerequisites = Vec::new();
+    let mut notes = Vec::new();
+
+    for (heading, body) in named {
+        if body.is_empty() || *body == MISSING_SECTION {
+            continue;
+        }
+        match field_for_heading(heading, mapping) {
+            Some(ArticleField::Problem) => problem.push(body.to_string()),
+            Some(ArticleField::Solution) => solution.push(body.to_string()),
+            Some(ArticleField::ExpectedResult) => expected_result.push(body.to_string()),
+            Some(ArticleField::Prerequisites) => prerequisites.push(body.to_string()),
+            // Sections like "Status" keep their heading so the notes still read well
+            Some(ArticleField::AdditionalNotes)
+                if default_field(heading) == Some(ArticleField::AdditionalNotes) =>
+            {
+                notes.push(body.to_string())
+            }
+            Some(ArticleField::AdditionalNotes) | None => {
+                notes.push(format!("**{}**\n\n{}", heading, body))
+            }
+        }
+    }
+
+    let join = |parts: Vec<String>| parts.join("\n\n");
+    let optional = |parts: Vec<String>| (!parts.is_empty()).then(|| parts.join("\n\n"));
+
+    ArticleFields {
+        title: title.to_string(),
+        problem: join(problem),
+        solution: join(solution),
+        expected_result: optional(expected_result),
+        prerequisites: optional(prerequisites),
+        additional_notes: optional(notes),
+    }
+}
+
+/// Put the title into the template's level-1 heading, e.g. "Known Issue: {title}"
+fn render_title(output_structure: &str, title: &str) -> String {
+    let pattern = sections::parse_sections(output_structure)
+        .into_iter()
+        .find(|s| s.level == 1)
+        .map(|s| s.heading)
+        .filter(|h| h.contains("{title}"))
+        .unwrap_or_else(|| "{title}".to_string());
+
+    // Models sometimes include the template's prefix themselves
+    let prefix = pattern.split("{title}").next().unwrap_or("");
+    if !prefix.is_empty() && title.to_lowercase().starts_with(&prefix.to_lowercase()) {
+        return title.to_string();
+    }
+    pattern.replace("{title}", title)
+}
+
+fn render_markdown(title: &str, bodies: &[(TemplateSection, String)]) -> String {
+    let mut markdown = format!("# {}\n", title);
+    for (section, body) in bodies {
+        let body = if body.is_empty() {
+            MISSING_SECTION
+        } else {
+            body
+        };
+        markdown.push_str(&format!(
+            "\n{} {}\n\n{}\n",
+            "#".repeat(section.level as usize),
+            section.heading,
+            body
+        ));
+    }
+    markdown
+}
+
+/// A draft holding `fields`, before grounding, provenance and the like
+/// are filled in
+pub fn into_draft(
+    fields: ArticleFields,
+    tags: Vec<String>,
+    content_markdown: String,
+    structured: bool,
+) -> ArticleDraft {
+    ArticleDraft {
+        title: fields.title,
+        problem: fields.problem,
+        solution: fields.solution,
+        expected_result: fields.expected_result,
+        prerequisites: fields.prerequisites,
+        additional_notes: fields.additional_notes,
+        tags,
+        content_markdown,
+        structured,
+        ungrounded: Vec::new(),
+        provenance_id: None,
+        references: Vec::new(),
+        redactions: Vec::new(),
+        warnings: Vec::new(),
+        language: None,
+    }
+}
+
+/// Models sometimes wrap JSON in a ```json fence even when told not to
+fn strip_code_fence(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+            rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+        }
+        None => trimmed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(output_structure: &str) -> Template {
+        Template {
+            id: "tpl-known-issue".to_string(),
+            name: "Known Issue".to_string(),
+            slug: "known-issue".to_string(),
+            description: String::new(),
+            system_prompt: String::new(),
+            output_structure: output_structure.to_string(),
+            field_mapping: Default::default(),
+            generation: Default::default(),
+            model: None,
+            is_builtin: true,
+            created_at: "2024-01-01".to_string(),
+        }
+    }
+
+    const KNOWN_ISSUE: &str = "# Known Issue: {title}\n\n## Symptoms\n\n## Affected Systems\n\n## Workaround\n\n## Status\n\n## Related Tickets\n";
+
+    #[test]
+    fn test_template_sections() {
+        let sections = template_sections(KNOWN_ISSUE);
+        let headings: Vec<_> = sections.iter().map(|s| s.heading.as_str()).collect();
+        assert_eq!(
+            headings,
+            vec![
+                "Symptoms",
+                "Affected Systems",
+                "Workaround",
+                "Status",
+                "Related Tickets"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_schema_requires_every_section() {
+        let schema = output_schema(&template_sections(KNOWN_ISSUE));
+        let required = schema["properties"]["sections"]["required"]
+            .as_array()
+            .unwrap();
+        assert_eq!(required.len(), 5);
+        assert_eq!(
+            schema["properties"]["sections"]["properties"]["Workaround"]["type"],
+            "string"
+        );
+    }
+
+    #[test]
+    fn test_draft_from_json_renders_template() {
+        let raw = r#"{
+            "title": "PDF export drops images",
+            "sections": {
+                "Symptoms": "Images are missing from exported PDFs.",
+                "Affected Systems": "- Version 4.2",
+                "Workaround": "1. Export to HTML\n2. Print to PDF",
+                "Status": "",
+                "Related Tickets": "TEST-1"
+            },
+            "tags": ["pdf", "export", " "]
+        }"#;
+
+        let draft = draft_from_json(raw, &template(KNOWN_ISSUE)).unwrap();
+
+        assert!(draft.structured);
+        assert_eq!(draft.title, "Known Issue: PDF export drops images");
+        assert_eq!(draft.problem, "Images are missing from exported PDFs.");
+        assert_eq!(draft.solution, "1. Export to HTML\n2. Print to PDF");
+        assert_eq!(draft.prerequisites.as_deref(), Some("- Version 4.2"));
+        assert_eq!(draft.tags, vec!["pdf", "export"]);
+        assert!(draft
+            .content_markdown
+            .starts_with("# Known Issue: PDF export drops images\n\n## Symptoms\n\nImages"));
+        assert!(draft
+            .content_markdown
+            .contains("## Status\n\n[Not available in ticket - please add]\n"));
+        // Unmapped sections land in the notes under their heading
+        assert!(draft
+            .additional_notes
+            .unwrap()
+            .contains("**Related Tickets**\n\nTEST-1"));
+    }
+
+    #[test]
+    fn test_draft_from_json_does_not_double_title_prefix() {
+        let raw = r#"```json
+{"title": "Known Issue: PDF export", "sections": {"Symptoms": "Broken"}, "tags": []}
+```"#;
+        let draft = draft_from_json(raw, &template(KNOWN_ISSUE)).unwrap();
+        assert_eq!(draft.title, "Known Issue: PDF export");
+    }
+
+    #[test]
+    fn test_draft_from_json_rejects_non_compliant_output() {
+        assert!(draft_from_json("# Title\n\n## Problem\nText", &template(KNOWN_ISSUE)).is_none());
+        assert!(draft_from_json(r#"{"title": "x"}"#, &template(KNOWN_ISSUE)).is_none());
+    }
+
+    #[test]
+    fn test_fields_from_markdown() {
+        let markdown = "# VPN drops\n\n## Problem\nThe VPN disconnects.\n\n## Environment\n- Windows 11\n\n## Cause\nExpired cert\n\n## Resolution\n1. Renew\n\n### Notes\nOnly on laptops\n\n## Expected Result\nIt stays up.\n";
+        let fields = fields_from_markdown(markdown, &BTreeMap::new());
+
+        assert_eq!(fields.title, "VPN drops");
+        assert_eq!(fields.problem, "The VPN disconnects.");
+        assert_eq!(fields.solution, "1. Renew\n\n### Notes\nOnly on laptops");
+        assert_eq!(fields.expected_result.as_deref(), Some("It stays up."));
+        assert_eq!(fields.prerequisites.as_deref(), Some("- Windows 11"));
+        assert_eq!(
+            fields.additional_notes.as_deref(),
+            Some("**Cause**\n\nExpired cert")
+        );
+    }
+
+    #[test]
+    fn test_field_for_heading() {
+        let none = BTreeMap::new();
+        assert_eq!(
+            field_for_heading("Resolution", &none),
+            Some(ArticleField::Solution)
+        );
+        assert_eq!(
+            field_for_heading("Answer", &none),
+            Some(ArticleField::Solution)
+        );
+        assert_eq!(
+            field_for_heading("Verification", &none),
+            Some(ArticleField::ExpectedResult)
+        );
+        assert_eq!(
+            field_for_heading("Troubleshooting Steps", &none),
+            Some(ArticleField::Solution)
+        );
+        assert_eq!(field_for_heading("Cause", &none), None);
+    }
+
+    #[test]
+    fn test_template_mapping_overrides_defaults() {
+        let mapping = BTreeMap::from([
+            ("Cause".to_string(), ArticleField::Problem),
+            ("Steps".to_string(), ArticleField::AdditionalNotes),
+        ]);
+        assert_eq!(
+            field_for_heading("cause", &mapping),
+            Some(ArticleField::Problem)
+        );
+        assert_eq!(
+            field_for_heading("Steps", &mapping),
+            Some(ArticleField::AdditionalNotes)
+        );
+        assert_eq!(
+            field_for_heading("Resolution", &mapping),
+            Some(ArticleField::Solution)
+        );
+    }
+
+    #[test]
+    fn test_sync_fields_follows_edited_markdown() {
+        let mut known_issue = template(KNOWN_ISSUE);
+        known_issue.field_mapping = BTreeMap::from([
+            ("Symptoms".to_string(), ArticleField::Problem),
+            ("Affected Systems".to_string(), ArticleField::Prerequisites),
+            ("Workaround".to_string(), ArticleField::Solution),
+            ("Status".to_string(), ArticleField::AdditionalNotes),
+        ]);
+
+        let mut article = NewArticle {
+            ticket_key: Some("TEST-1".to_string()),
+            source_ticket_keys: vec![],
+            title: "Old title".to_string(),
+            problem: "Stale problem".to_string(),
+            solution: "Stale solution".to_string(),
+            expected_result: Some("Stale result".to_string()),
+            prerequisites: None,
+            additional_notes: None,
+            tags: vec![],
+            content_markdown: "# Known Issue: PDF export\n\n## Symptoms\nImages vanish.\n\n## Affected Systems\n- 4.2\n\n## Workaround\nExport to HTML.\n\n## Status\nFix planned.\n".to_string(),
+            template_id: Some("tpl-known-issue".to_string()),
+            provenance_id: None,
+            language: None,
+            translation_of: None,
+        };
+
+        sync_fields(&mut article, Some(&known_issue));
+
+        assert_eq!(article.title, "Known Issue: PDF export");
+        assert_eq!(article.problem, "Images vanish.");
+        assert_eq!(article.solution, "Export to HTML.");
+        assert_eq!(article.prerequisites.as_deref(), Some("- 4.2"));
+        assert_eq!(article.expected_result, None);
+        assert_eq!(
+            article.additional_notes.as_deref(),
+            Some("**Status**\n\nFix planned.")
+        );
+    }
+
+    #[test]
+    fn test_sync_fields_ignores_markdown_without_sections() {
+        let mut article = NewArticle {
+            ticket_key: None,
+            source_ticket_keys: vec![],
+            title: "Title".to_string(),
+            problem: "Problem".to_string(),
+            solution: "Solution".to_string(),
+            expected_result: None,
+            prerequisites: None,
+            additional_notes: None,
+            tags: vec![],
+            content_markdown: "Just a paragraph".to_string(),
+            template_id: None,
+            provenance_id: None,
+            language: None,
+            translation_of: None,
+        };
+
+        sync_fields(&mut article, None);
+        assert_eq!(article.problem, "Problem");
+        assert_eq!(article.solution, "Solution");
+    }
+}
@@ -0,0 +1,208 @@
+use crate::error::AppError;
+use crate::models::{AuthMode, Credentials};
+use reqwest::RequestBuilder;
+use std::future::Future;
+
+/// Hosts of Atlassian Cloud sites, which only accept Basic auth with an API token
+const CLOUD_HOST_SUFFIXES: &[&str] = &[".atlassian.net", ".jira.com"];
+
+/// Sign a Jira or Confluence request with the stored credentials
+pub trait Authorize {
+    fn authorize(self, credentials: &Credentials) -> Self;
+}
+
+impl Authorize for RequestBuilder {
+    fn authorize(self, credentials: &Credentials) -> Self {
+        match credentials {
+            Credentials::Pat { token } => self.bearer_auth(token),
+            Credentials::Basic { email, token } => self.basic_auth(email, Some(token)),
+        }
+    }
+}
+
+/// Whether `base_url` points at an Atlassian Cloud site
+pub fn is_cloud(base_url: &str) -> bool {
+    let host = base_url
+        .split("://")
+        .last()
+        .unwrap_or(base_url)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    CLOUD_HOST_SUFFIXES
+        .iter()
+        .any(|suffix| host.ends_with(suffix))
+}
+
+/// The credentials to try, in order, for `mode`. In `Auto` mode, Cloud
+/// sites get Basic auth only. Other servers get the token as a PAT first,
+/// then Basic auth when an email is given: there the email is rarely the
+/// username, and every rejected Basic login counts towards the account's
+/// CAPTCHA lockout.
+pub fn candidates(
+    base_url: &str,
+    mode: AuthMode,
+    email: Option<&str>,
+    token: &str,
+) -> Result<Vec<Credentials>, AppError> {
+    let email = email.map(str::trim).filter(|e| !e.is_empty());
+    let pat = Credentials::Pat {
+        token: token.to_string(),
+    };
+    let basic = |email: &str| Credentials::Basic {
+        email: email.to_string(),
+        token: token.to_string(),
+    };
+
+    match (mode, email) {
+        (AuthMode::Pat, _) => Ok(vec![pat]),
+        (AuthMode::Basic, Some(email)) => Ok(vec![basic(email)]),
+        (AuthMode::Basic, None) => Err(AppError::Internal(
+            "Basic auth needs the account email along with the API token".to_string(),
+        )),
+        (AuthMode::Auto, Some(email)) if is_cloud(base_url) => Ok(vec![basic(email)]),
+        (AuthMode::Auto, Some(email)) => Ok(vec![pat, basic(email)]),
+        (AuthMode::Auto, None) if is_cloud(base_url) => Err(AppError::Internal(
+            "Atlassian Cloud needs the account email along with an API token".to_string(),
+        )),
+        (AuthMode::Auto, None) => Ok(vec![pat]),
+    }
+}
+
+/// Try each of `candidates` with `probe` and return the first one the
+/// server accepts. Rejected credentials move on to the next candidate;
+/// any other error ends the search.
+pub async fn detect<F, Fut>(
+    service: &str,
+    candidates: Vec<Credentials>,
+    probe: F,
+) -> Result<Credentials, AppError>
+where
+    F: Fn(Credentials) -> Fut,
+    Fut: Future<Output = Result<bool, AppError>>,
+{
+    for credentials in candidates {
+        match probe(credentials.clone()).await {
+            Ok(true) => return Ok(credentials),
+            Ok(false) | Err(AppError::TokenMissing { .. }) => {
+                log::info!("{} rejected {:?}", service, credentials);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(AppError::TokenMissing {
+        service: service.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::AUTHORIZATION;
+
+    #[test]
+    fn test_authorize() {
+        let client = reqwest::Client::new();
+        let header = |credentials: &Credentials| {
+            let request = client
+                .get("http://test")
+                .authorize(credentials)
+                .build()
+                .unwrap();
+            request.headers()[AUTHORIZATION]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let pat = Credentials::Pat {
+            token: "abc123".to_string(),
+        };
+        let basic = Credentials::Basic {
+            email: "me@example.com".to_string(),
+            token: "abc123".to_string(),
+        };
+        assert_eq!(header(&pat), "Bearer abc123");
+        // base64("me@example.com:abc123")
+        assert_eq!(header(&basic), "Basic bWVAZXhhbXBsZS5jb206YWJjMTIz");
+    }
+
+    #[test]
+    fn test_candidates() {
+        let modes = |url: &str, mode: AuthMode, email: Option<&str>| {
+            candidates(url, mode, email, "token")
+                .map(|list| list.iter().map(Credentials::mode).collect::<Vec<_>>())
+        };
+        let cloud = "https://acme.atlassian.net/wiki";
+        let server = "https://jira.acme.local:8443";
+
+        assert_eq!(
+            modes(server, AuthMode::Auto, Some("me@acme.com")).unwrap(),
+            vec![AuthMode::Pat, AuthMode::Basic]
+        );
+        assert_eq!(
+            modes(server, AuthMode::Auto, Some(" ")).unwrap(),
+            vec![AuthMode::Pat]
+        );
+        assert_eq!(
+            modes(cloud, AuthMode::Auto, Some("me@acme.com")).unwrap(),
+            vec![AuthMode::Basic]
+        );
+        assert_eq!(
+            modes(cloud, AuthMode::Pat, None).unwrap(),
+            vec![AuthMode::Pat]
+        );
+        assert!(modes(cloud, AuthMode::Auto, None).is_err());
+        assert!(modes(server, AuthMode::Basic, None).is_err());
+    }
+
+    #[test]
+    fn test_is_cloud() {
+        assert!(is_cloud("https://acme.atlassian.net"));
+        assert!(is_cloud("https://ACME.atlassian.net/wiki/"));
+        assert!(!is_cloud("https://jira.acme.com"));
+        assert!(!is_cloud("https://atlassian.net.evil.example"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_falls_back_to_basic() {
+        let candidates = vec![
+            Credentials::Pat {
+                token: "token".to_string(),
+            },
+            Credentials::Basic {
+                email: "me@acme.com".to_string(),
+                token: "token".to_string(),
+            },
+        ];
+
+        let found = detect("jira", candidates.clone(), |c| async move {
+            match c {
+                Credentials::Pat { .. } => Err(AppError::TokenMissing {
+                    service: "jira".to_string(),
+                }),
+                Credentials::Basic { .. } => Ok(true),
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(found.mode(), AuthMode::Basic);
+
+        let rejected = detect("jira", candidates.clone(), |_| async { Ok(false) }).await;
+        assert!(matches!(rejected, Err(AppError::TokenMissing { .. })));
+
+        let unreachable = detect("jira", candidates, |_| async {
+            Err(AppError::Jira {
+                status: 503,
+                message: "Unavailable".to_string(),
+            })
+        })
+        .await;
+        assert!(matches!(
+            unreachable,
+            Err(AppError::Jira { status: 503, .. })
+        ));
+    }
+}
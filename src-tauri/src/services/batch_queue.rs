@@ -0,0 +1,139 @@
+use crate::error::AppError;
+use crate::models::{ArticleDraft, NewArticle};
+
+/// Tickets drafted at the same time when the caller does not say. A local
+/// model serves one request at a time anyway; two keeps it busy while the
+/// next ticket is fetched.
+pub const DEFAULT_CONCURRENCY: usize = 2;
+
+/// Upper bound on the concurrency a batch can ask for
+pub const MAX_CONCURRENCY: usize = 8;
+
+/// Attempts per ticket when the caller does not say
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Most tickets a single JQL search is allowed to queue
+pub const MAX_BATCH_TICKETS: usize = 500;
+
+/// Delay before the first retry; doubled for every further attempt
+const RETRY_BASE_SECS: u64 = 30;
+
+/// Longest delay between two attempts
+const RETRY_MAX_SECS: u64 = 15 * 60;
+
+/// Seconds to wait before retrying a job that failed its `attempt`-th try
+/// (1-based), or `None` if it should not be retried
+pub fn retry_delay(error: &AppError, attempt: u32, max_attempts: u32) -> Option<u64> {
+    if attempt >= max_attempts || !is_retryable(error) {
+        return None;
+    }
+    let delay = RETRY_BASE_SECS.saturating_mul(1 << (attempt.saturating_sub(1)).min(16));
+    Some(delay.min(RETRY_MAX_SECS))
+}
+
+/// Whether trying again can help. Missing tickets, denied access, a missing
+/// token or a bad template fail the same way every time.
+pub fn is_retryable(error: &AppError) -> bool {
+    match error {
+        AppError::Jira { status, .. } => *status == 429 || *status >= 500,
+        AppError::Network(_)
+        | AppError::OllamaUnavailable { .. }
+        | AppError::LlmUnavailable { .. }
+        | AppError::Internal(_) => true,
+        AppError::Confluence { .. }
+        | AppError::TokenMissing { .. }
+        | AppError::Database(_)
+        | AppError::Cancelled
+        | AppError::Conversion(_) => false,
+    }
+}
+
+/// The draft of one batch job, ready to be saved
+pub fn new_article(
+    draft: ArticleDraft,
+    ticket_key: &str,
+    template_id: &str,
+    provenance_id: Option<i64>,
+) -> NewArticle {
+    NewArticle {
+        ticket_key: Some(ticket_key.to_string()),
+        source_ticket_keys: vec![ticket_key.to_string()],
+        title: draft.title,
+        problem: draft.problem,
+        solution: draft.solution,
+        expected_result: draft.expected_result,
+        prerequisites: draft.prerequisites,
+        additional_notes: draft.additional_notes,
+        tags: draft.tags,
+        content_markdown: draft.content_markdown,
+        template_id: Some(template_id.to_string()),
+        provenance_id,
+        language: draft.language,
+        translation_of: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let error = AppError::OllamaUnavailable {
+            url: "http://localhost:11434".to_string(),
+        };
+        assert_eq!(retry_delay(&error, 1, 5), Some(30));
+        assert_eq!(retry_delay(&error, 2, 5), Some(60));
+        assert_eq!(retry_delay(&error, 3, 5), Some(120));
+        assert_eq!(retry_delay(&error, 5, 5), None);
+        assert_eq!(retry_delay(&error, 10, 20), Some(RETRY_MAX_SECS));
+    }
+
+    #[test]
+    fn test_permanent_errors_are_not_retried() {
+        let not_found = AppError::Jira {
+            status: 404,
+            message: "Ticket SUP-1 not found".to_string(),
+        };
+        assert_eq!(retry_delay(&not_found, 1, 3), None);
+
+        let rate_limited = AppError::Jira {
+            status: 429,
+            message: "Too many requests".to_string(),
+        };
+        assert!(retry_delay(&rate_limited, 1, 3).is_some());
+
+        let no_token = AppError::TokenMissing {
+            service: "jira".to_string(),
+        };
+        assert!(!is_retryable(&no_token));
+        assert!(!is_retryable(&AppError::Cancelled));
+    }
+
+    #[test]
+    fn test_new_article_from_draft() {
+        let draft = ArticleDraft {
+            title: "VPN drops".to_string(),
+            problem: "Tunnel resets".to_string(),
+            solution: "Raise the keepalive".to_string(),
+            expected_result: None,
+            prerequisites: None,
+            additional_notes: None,
+            tags: vec!["vpn".to_string()],
+            content_markdown: "# VPN drops".to_string(),
+            structured: true,
+            ungrounded: vec![],
+            provenance_id: Some(7),
+            references: vec![],
+            redactions: vec![],
+            warnings: vec![],
+            language: None,
+        };
+
+        let article = new_article(draft, "SUP-12", "tpl-troubleshoot", Some(7));
+        assert_eq!(article.source_keys(), vec!["SUP-12"]);
+        assert_eq!(article.template_id.as_deref(), Some("tpl-troubleshoot"));
+        assert_eq!(article.provenance_id, Some(7));
+        assert_eq!(article.tags, vec!["vpn"]);
+    }
+}
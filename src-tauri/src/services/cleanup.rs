@@ -0,0 +1,398 @@
+use regex::Regex;
+
+/// One step of the cleanup pipeline run over model output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// Remove `<think>...</think>` reasoning emitted by deepseek-r1, qwq and the like
+    StripThinkBlocks,
+    /// Remove "Here's the article:" openers and "Let me know..." sign-offs
+    StripChatter,
+    /// Unwrap an answer sent as one big ```markdown code block
+    UnwrapOuterFence,
+    /// Close a code block the model left open
+    CloseCodeBlocks,
+    /// Fix skipped heading levels and repeated top-level headings
+    NormalizeHeadings,
+    /// Number ordered list items 1, 2, 3 instead of 1, 1, 1
+    RenumberLists,
+    /// Collapse runs of blank lines into one
+    CollapseBlankLines,
+}
+
+impl Pass {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Pass::StripThinkBlocks => strip_think_blocks(text),
+            Pass::StripChatter => strip_chatter(text),
+            Pass::UnwrapOuterFence => unwrap_outer_fence(text),
+            Pass::CloseCodeBlocks => close_code_blocks(text),
+            Pass::NormalizeHeadings => normalize_headings(text),
+            Pass::RenumberLists => renumber_lists(text),
+            Pass::CollapseBlankLines => collapse_blank_lines(text),
+        }
+    }
+}
+
+/// An ordered list of cleanup passes. The default runs all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    passes: Vec<Pass>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        // Think blocks and chatter go first: they hide the fence and headings
+        Self::new(vec![
+            Pass::StripThinkBlocks,
+            Pass::StripChatter,
+            Pass::UnwrapOuterFence,
+            Pass::CloseCodeBlocks,
+            Pass::NormalizeHeadings,
+            Pass::RenumberLists,
+            Pass::CollapseBlankLines,
+        ])
+    }
+}
+
+impl Pipeline {
+    pub fn new(passes: Vec<Pass>) -> Self {
+        Self { passes }
+    }
+
+    pub fn run(&self, raw: &str) -> String {
+        let cleaned = self
+            .passes
+            .iter()
+            .fold(raw.to_string(), |text, pass| pass.apply(&text));
+        cleaned.trim().to_string()
+    }
+}
+
+/// Remove reasoning blocks. A closing tag without an opening one means the
+/// chat template opened the block in the prompt, so everything before it
+/// is reasoning too.
+pub fn strip_think_blocks(text: &str) -> String {
+    let blocks = Regex::new(r"(?is)<(think|thinking)>.*?</(think|thinking)>").unwrap();
+    let text = blocks.replace_all(text, "");
+
+    let unopened = Regex::new(r"(?is)^.*?</(think|thinking)>").unwrap();
+    let text = unopened.replace(&text, "");
+
+    // Output cut off mid-thought has no article in it
+    let unclosed = Regex::new(r"(?is)<(think|thinking)>.*$").unwrap();
+    unclosed.replace(&text, "").trim_start().to_string()
+}
+
+/// Remove a chatty first line and a trailing sign-off
+pub fn strip_chatter(text: &str) -> String {
+    let mut cleaned = text.to_string();
+
+    // Remove common preambles (first line only if it matches)
+    let preamble_re = Regex::new(r"(?i)^(here'?s?|i'?ve|sure|certainly|of course).*?\n").unwrap();
+    if let Some(mat) = preamble_re.find(&cleaned) {
+        if mat.start() == 0 {
+            cleaned = cleaned[mat.end()..].to_string();
+        }
+    }
+
+    // Remove trailing sign-offs
+    let signoff_re =
+        Regex::new(r"(?i)\n(let me know|feel free|i hope|is there anything).*$").unwrap();
+    signoff_re.replace(&cleaned, "").to_string()
+}
+
+/// Unwrap text that is entirely one fenced block. Only ```markdown and ```md
+/// fences are unwrapped unconditionally; a bare ``` fence is only unwrapped
+/// when it holds headings, so a genuine code answer is left alone.
+pub fn unwrap_outer_fence(text: &str) -> String {
+    let trimmed = text.trim();
+    let Some((first, rest)) = trimmed.split_once('\n') else {
+        return text.to_string();
+    };
+    let Some(body) = rest.trim_end().strip_suffix("```") else {
+        return text.to_string();
+    };
+
+    let language = match first.trim().strip_prefix("```") {
+        Some(language) => language.trim().to_lowercase(),
+        None => return text.to_string(),
+    };
+    let unwrap = match language.as_str() {
+        "markdown" | "md" => true,
+        "" => body.lines().any(|l| heading_level(l).is_some()),
+        _ => false,
+    };
+
+    if unwrap {
+        body.trim_end().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Close a code block left open, e.g. when generation hit the token limit
+pub fn close_code_blocks(text: &str) -> String {
+    let mut cleaned = text.to_string();
+    // Odd number of fences means unclosed code block
+    if cleaned.match_indices("```").count() % 2 == 1 {
+        cleaned.push_str("\n```\n");
+    }
+    cleaned
+}
+
+/// Make the heading hierarchy consistent: headings never go more than one
+/// level deeper than the one before, and when the text opens with an H1
+/// title, later H1s (and their subsections) move one level down.
+pub fn normalize_headings(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    let mut title_is_h1 = None;
+    let mut offset = 0;
+    // (level as written, level output) of the enclosing headings
+    let mut parents: Vec<(usize, usize)> = Vec::new();
+
+    for line in text.lines() {
+        if is_fence(line) {
+            in_code_block = !in_code_block;
+        }
+        let written = match heading_level(line) {
+            Some(level) if !in_code_block => level,
+            _ => {
+                lines.push(line.to_string());
+                continue;
+            }
+        };
+
+        let level = match title_is_h1 {
+            None => {
+                title_is_h1 = Some(written == 1);
+                written
+            }
+            Some(true) if written == 1 => {
+                offset = 1;
+                written + offset
+            }
+            Some(_) => (written + offset).min(6),
+        };
+        while parents.last().is_some_and(|(parent, _)| *parent >= level) {
+            parents.pop();
+        }
+        let new_level = match parents.last() {
+            Some((_, parent_level)) => parent_level + 1,
+            None => level,
+        };
+        parents.push((level, new_level));
+
+        let heading = line.trim_start()[written..]
+            .trim()
+            .trim_end_matches('#')
+            .trim_end();
+        lines.push(format!("{} {}", "#".repeat(new_level), heading));
+    }
+
+    lines.join("\n")
+}
+
+/// Renumber ordered lists so items count up from the first item's number.
+/// Lists continue across blank lines, indented continuation lines and code
+/// blocks between steps; nested lists are numbered on their own.
+pub fn renumber_lists(text: &str) -> String {
+    let item_re = Regex::new(r"^(\s*)(\d{1,9})([.)])(\s+.*)$").unwrap();
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    // (indent, next number) of each open list, outermost first
+    let mut counters: Vec<(usize, u64)> = Vec::new();
+
+    for line in text.lines() {
+        if is_fence(line) {
+            in_code_block = !in_code_block;
+            lines.push(line.to_string());
+            continue;
+        }
+        if in_code_block || line.trim().is_empty() {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        counters.retain(|(list_indent, _)| *list_indent <= indent);
+
+        match item_re.captures(line) {
+            Some(caps) => {
+                let number = match counters.last_mut() {
+                    Some((list_indent, next)) if *list_indent == indent => {
+                        let number = *next;
+                        *next += 1;
+                        number
+                    }
+                    _ => {
+                        let start = caps[2].parse().unwrap_or(1);
+                        counters.push((indent, start + 1));
+                        start
+                    }
+                };
+                lines.push(format!("{}{}{}{}", &caps[1], number, &caps[3], &caps[4]));
+            }
+            None => {
+                // A paragraph or heading at the list's own level ends it
+                if indent == 0 || heading_level(line).is_some() {
+                    counters.clear();
+                }
+                lines.push(line.to_string());
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Collapse runs of blank lines outside code blocks into a single one
+pub fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        if is_fence(line) {
+            in_code_block = !in_code_block;
+        }
+        let blank = line.trim().is_empty();
+        if blank && !in_code_block && lines.last().is_some_and(|l| l.trim().is_empty()) {
+            continue;
+        }
+        lines.push(if blank && !in_code_block { "" } else { line });
+    }
+
+    lines.join("\n")
+}
+
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Level of an ATX heading (`## Title`), if the line is one
+fn heading_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let after = &trimmed[level..];
+    if (1..=6).contains(&level) && (after.is_empty() || after.starts_with(' ')) {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_think_blocks() {
+        let input = "<think>\nThe user wants a KB article.\n</think>\n\n# VPN drops";
+        assert_eq!(strip_think_blocks(input), "# VPN drops");
+
+        // Reasoning opened by the chat template, only the closing tag is emitted
+        let input = "Okay, let me plan the sections.\n</think>\n# VPN drops";
+        assert_eq!(strip_think_blocks(input), "# VPN drops");
+
+        let input = "# VPN drops\n<Thinking>check the port</Thinking>\nRestart the client.";
+        assert_eq!(
+            strip_think_blocks(input),
+            "# VPN drops\n\nRestart the client."
+        );
+
+        assert_eq!(strip_think_blocks("<think>still going"), "");
+    }
+
+    #[test]
+    fn test_unwrap_outer_fence() {
+        let input = "```markdown\n# Title\n\n```bash\nls\n```\n\nDone\n```";
+        assert_eq!(
+            unwrap_outer_fence(input),
+            "# Title\n\n```bash\nls\n```\n\nDone"
+        );
+
+        assert_eq!(
+            unwrap_outer_fence("```\n# Title\nBody\n```"),
+            "# Title\nBody"
+        );
+
+        // A real code answer stays fenced
+        let code = "```\nsystemctl restart nginx\n```";
+        assert_eq!(unwrap_outer_fence(code), code);
+        let code = "```python\n# comment\nprint(1)\n```";
+        assert_eq!(unwrap_outer_fence(code), code);
+        let partial = "```markdown\n# Title\n```\nMore text";
+        assert_eq!(unwrap_outer_fence(partial), partial);
+    }
+
+    #[test]
+    fn test_normalize_headings_fixes_skipped_levels() {
+        let input = "# Title\n\n### Problem\nText\n##### Detail\n### Solution";
+        assert_eq!(
+            normalize_headings(input),
+            "# Title\n\n## Problem\nText\n### Detail\n## Solution"
+        );
+    }
+
+    #[test]
+    fn test_normalize_headings_demotes_repeated_h1() {
+        let input = "# Title\n# Problem\n## Cause\n# Solution\n```bash\n# not a heading\n```";
+        assert_eq!(
+            normalize_headings(input),
+            "# Title\n## Problem\n### Cause\n## Solution\n```bash\n# not a heading\n```"
+        );
+    }
+
+    #[test]
+    fn test_normalize_headings_keeps_section_bodies() {
+        // A regenerated section body starts below H1 and stays there
+        let input = "### Notes\nText\n#### More";
+        assert_eq!(normalize_headings(input), input);
+        assert_eq!(normalize_headings("## Steps ##"), "## Steps");
+        assert_eq!(normalize_headings("#hashtag"), "#hashtag");
+    }
+
+    #[test]
+    fn test_renumber_lists() {
+        let input = "1. Open settings\n1. Click VPN\n\n   Wait for it.\n1. Save";
+        assert_eq!(
+            renumber_lists(input),
+            "1. Open settings\n2. Click VPN\n\n   Wait for it.\n3. Save"
+        );
+    }
+
+    #[test]
+    fn test_renumber_lists_nested_and_separated() {
+        let input = "1. Stop the service:\n```bash\n1. systemctl stop vpn\n```\n1. Edit the config\n   1) Set port\n   1) Set mtu\n1. Start it\n\nNext paragraph\n\n1. New list";
+        assert_eq!(
+            renumber_lists(input),
+            "1. Stop the service:\n```bash\n1. systemctl stop vpn\n```\n2. Edit the config\n   1) Set port\n   2) Set mtu\n3. Start it\n\nNext paragraph\n\n1. New list"
+        );
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        let input = "# Title\n\n\n\nText\n  \n\n```\na\n\n\nb\n```";
+        assert_eq!(
+            collapse_blank_lines(input),
+            "# Title\n\nText\n\n```\na\n\n\nb\n```"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_runs_every_pass() {
+        let raw = "<think>plan</think>\nHere's the article:\n```markdown\n# VPN drops\n\n\n### Resolution\n1. Restart\n1. Reconnect\n```\nLet me know if this helps!";
+        assert_eq!(
+            Pipeline::default().run(raw),
+            "# VPN drops\n\n## Resolution\n1. Restart\n2. Reconnect"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_runs_only_its_passes() {
+        let raw = "<think>plan</think>\n1. One\n1. Two";
+        let pipeline = Pipeline::new(vec![Pass::StripThinkBlocks]);
+        assert_eq!(pipeline.run(raw), "1. One\n1. Two");
+    }
+}
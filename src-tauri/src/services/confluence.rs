@@ -1,10 +1,13 @@
 use crate::error::AppError;
 use crate::models::confluence::{ConfluenceSpace, PublishResult};
+use crate::models::Credentials;
+use crate::services::atlassian::Authorize;
+use crate::services::tag_suggester;
 use serde::{Deserialize, Serialize};
 
 pub struct ConfluenceClient {
     base_url: String,
-    pat: String,
+    credentials: Credentials,
 }
 
 #[derive(Debug, Serialize)]
@@ -90,8 +93,11 @@ struct VersionInfo {
 }
 
 impl ConfluenceClient {
-    pub fn new(base_url: String, pat: String) -> Self {
-        Self { base_url, pat }
+    pub fn new(base_url: String, credentials: Credentials) -> Self {
+        Self {
+            base_url,
+            credentials,
+        }
     }
 
     /// Test connection to Confluence
@@ -104,7 +110,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .get(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -121,7 +127,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .get(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -159,13 +165,15 @@ impl ConfluenceClient {
             self.base_url.trim_end_matches('/')
         );
 
+        // Confluence rejects labels with spaces or upper case characters
+        let labels = tag_suggester::normalize_labels(labels);
         let metadata = if !labels.is_empty() {
             Some(Metadata {
                 labels: labels
-                    .iter()
-                    .map(|l| Label {
+                    .into_iter()
+                    .map(|name| Label {
                         prefix: "global".to_string(),
-                        name: l.clone(),
+                        name,
                     })
                     .collect(),
             })
@@ -191,7 +199,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .post(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -245,7 +253,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .get(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -278,7 +286,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .get(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -326,7 +334,7 @@ impl ConfluenceClient {
         let client = reqwest::Client::new();
         let response = client
             .put(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.pat))
+            .authorize(&self.credentials)
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -0,0 +1,379 @@
+use crate::models::{ArticleField, ConformanceReport, RenamedSection, Template};
+use crate::services::article_fields::{self, TemplateSection, MISSING_SECTION};
+use crate::services::sections::{self, Section};
+use std::collections::{BTreeMap, HashSet};
+
+/// Similarity at which a draft heading counts as the template section it
+/// resembles, e.g. "Root Cause" for "Cause"
+const MATCH_THRESHOLD: f32 = 0.65;
+
+/// Headings whose words contain all of the other's, e.g. "Root Cause" and "Cause"
+const SUBSET_SIMILARITY: f32 = 0.85;
+
+/// Headings mapping to the same article field, e.g. "Symptoms" and "Problem"
+const SAME_FIELD_SIMILARITY: f32 = 0.7;
+
+/// Words that do not tell sections apart
+const FILLER_WORDS: &[&str] = &["a", "an", "and", "the", "of", "to", "for"];
+
+/// Compare the sections of `markdown` with the ones the template's
+/// `output_structure` asks for. Headings are matched fuzzily, so a renamed
+/// section is reported as renamed rather than as missing plus extra.
+pub fn check(markdown: &str, template: &Template) -> ConformanceReport {
+    let alignment = Alignment::new(markdown, template);
+
+    let mut renamed: Vec<(usize, RenamedSection)> = alignment
+        .pairs
+        .iter()
+        .filter(|(e, f)| alignment.expected[*e].heading != alignment.found[*f].heading)
+        .map(|(e, f)| {
+            (
+                *e,
+                RenamedSection {
+                    expected: alignment.expected[*e].heading.clone(),
+                    found: alignment.found[*f].heading.clone(),
+                },
+            )
+        })
+        .collect();
+    renamed.sort_by_key(|(e, _)| *e);
+
+    ConformanceReport {
+        missing: alignment
+            .missing()
+            .into_iter()
+            .map(|e| alignment.expected[e].heading.clone())
+            .collect(),
+        extra: (0..alignment.found.len())
+            .filter(|f| !alignment.pairs.iter().any(|(_, pf)| pf == f))
+            .map(|f| alignment.found[f].heading.clone())
+            .collect(),
+        out_of_order: alignment
+            .out_of_order()
+            .into_iter()
+            .map(|e| alignment.expected[e].heading.clone())
+            .collect(),
+        renamed: renamed.into_iter().map(|(_, r)| r).collect(),
+    }
+}
+
+/// Warnings for `QualityScore`, one per problem found
+pub fn warnings(report: &ConformanceReport) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for heading in &report.missing {
+        warnings.push(format!("Missing template section: {}", heading));
+    }
+    for section in &report.renamed {
+        warnings.push(format!(
+            "Section \"{}\" should be named \"{}\"",
+            section.found, section.expected
+        ));
+    }
+    for heading in &report.out_of_order {
+        warnings.push(format!("Section out of template order: {}", heading));
+    }
+    for heading in &report.extra {
+        warnings.push(format!("Section not in template: {}", heading));
+    }
+    warnings
+}
+
+/// Add every missing template section to `markdown`, with the placeholder
+/// body used for information the ticket does not have. Each is inserted
+/// after the section that precedes it in the template, or before the one
+/// that follows it; the rest of the document is left as it is.
+pub fn insert_missing(markdown: &str, template: &Template) -> String {
+    let alignment = Alignment::new(markdown, template);
+    let matched = |e: usize| {
+        alignment
+            .pairs
+            .iter()
+            .find(|(pe, _)| *pe == e)
+            .map(|(_, f)| *f)
+    };
+
+    // Missing sections by the offset they go in at, in template order
+    let mut inserts: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for e in alignment.missing() {
+        let offset = (0..e)
+            .rev()
+            .find_map(matched)
+            .map(|f| alignment.found[f].end)
+            .or_else(|| {
+                (e + 1..alignment.expected.len())
+                    .find_map(matched)
+                    .map(|f| alignment.found[f].start)
+            })
+            .unwrap_or(markdown.len());
+        inserts.entry(offset).or_default().push(e);
+    }
+
+    // Insert back to front so earlier offsets stay valid
+    let mut result = markdown.to_string();
+    for (offset, missing) in inserts.into_iter().rev() {
+        let mut block = missing
+            .iter()
+            .map(|e| {
+                let section = &alignment.expected[*e];
+                format!(
+                    "{} {}\n\n{}\n",
+                    "#".repeat(section.level as usize),
+                    section.heading,
+                    MISSING_SECTION
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let before = &result[..offset];
+        let separator = if before.is_empty() || before.ends_with("\n\n") {
+            ""
+        } else if before.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        block.insert_str(0, separator);
+        if offset < result.len() {
+            block.push('\n');
+        }
+        result.insert_str(offset, &block);
+    }
+    result
+}
+
+/// Template sections paired with the draft sections that match them
+struct Alignment {
+    expected: Vec<TemplateSection>,
+    found: Vec<Section>,
+    /// (expected index, found index), one-to-one
+    pairs: Vec<(usize, usize)>,
+}
+
+impl Alignment {
+    fn new(markdown: &str, template: &Template) -> Self {
+        let expected = article_fields::template_sections(&template.output_structure);
+        let deepest = expected.iter().map(|s| s.level).max().unwrap_or(2);
+        // Subsections below the template's levels belong to their parent
+        let found: Vec<Section> = sections::parse_sections(markdown)
+            .into_iter()
+            .filter(|s| s.level > 1 && s.level <= deepest)
+            .collect();
+
+        // Best matches first, each section used once
+        let mut candidates: Vec<(f32, usize, usize)> = Vec::new();
+        for (e, expected_section) in expected.iter().enumerate() {
+            for (f, found_section) in found.iter().enumerate() {
+                let score = similarity(&expected_section.heading, &found_section.heading, template);
+                if score >= MATCH_THRESHOLD {
+                    candidates.push((score, e, f));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for (_, e, f) in candidates {
+            if !pairs.iter().any(|(pe, pf)| *pe == e || *pf == f) {
+                pairs.push((e, f));
+            }
+        }
+
+        Self {
+            expected,
+            found,
+            pairs,
+        }
+    }
+
+    fn missing(&self) -> Vec<usize> {
+        (0..self.expected.len())
+            .filter(|e| !self.pairs.iter().any(|(pe, _)| pe == e))
+            .collect()
+    }
+
+    /// Matched template sections that are not where the template puts them:
+    /// everything outside the longest run that is already in template order
+    fn out_of_order(&self) -> Vec<usize> {
+        let mut by_position = self.pairs.clone();
+        by_position.sort_by_key(|(_, f)| *f);
+        let order: Vec<usize> = by_position.iter().map(|(e, _)| *e).collect();
+
+        // Longest increasing subsequence; n is a handful of sections
+        let mut length = vec![1; order.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; order.len()];
+        for i in 0..order.len() {
+            for j in 0..i {
+                if order[j] < order[i] && length[j] + 1 > length[i] {
+                    length[i] = length[j] + 1;
+                    previous[i] = Some(j);
+                }
+            }
+        }
+
+        let mut in_order = HashSet::new();
+        let mut current = (0..order.len()).max_by_key(|i| (length[*i], std::cmp::Reverse(*i)));
+        while let Some(i) = current {
+            in_order.insert(order[i]);
+            current = previous[i];
+        }
+
+        let mut misplaced: Vec<usize> = order
+            .into_iter()
+            .filter(|e| !in_order.contains(e))
+            .collect();
+        misplaced.sort();
+        misplaced
+    }
+}
+
+/// How alike two section headings are, from 0 to 1
+fn similarity(expected: &str, found: &str, template: &Template) -> f32 {
+    let expected_words = words(expected);
+    let found_words = words(found);
+    if expected_words.is_empty() || found_words.is_empty() {
+        return 0.0;
+    }
+    if expected_words == found_words {
+        return 1.0;
+    }
+
+    let mut score = bigram_similarity(&expected_words.join(" "), &found_words.join(" "));
+
+    let expected_set: HashSet<&String> = expected_words.iter().collect();
+    let found_set: HashSet<&String> = found_words.iter().collect();
+    if expected_set.is_subset(&found_set) || found_set.is_subset(&expected_set) {
+        score = score.max(SUBSET_SIMILARITY);
+    }
+
+    let expected_field = article_fields::field_for_heading(expected, &template.field_mapping);
+    let found_field = article_fields::field_for_heading(found, &template.field_mapping);
+    if expected_field.is_some()
+        && expected_field == found_field
+        && expected_field != Some(ArticleField::AdditionalNotes)
+    {
+        score = score.max(SAME_FIELD_SIMILARITY);
+    }
+
+    score
+}
+
+fn words(heading: &str) -> Vec<String> {
+    heading
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| !w.is_empty() && !FILLER_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Dice coefficient of character bigrams, which forgives typos and plurals
+fn bigram_similarity(a: &str, b: &str) -> f32 {
+    let bigrams = |s: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let a = bigrams(a);
+    let mut b = bigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let total = (a.len() + b.len()) as f32;
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(i) = b.iter().position(|p| p == pair) {
+            b.swap_remove(i);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f32 / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn troubleshooting() -> Template {
+        Template {
+            id: "tpl-troubleshoot".to_string(),
+            name: "Troubleshooting".to_string(),
+            slug: "troubleshooting".to_string(),
+            description: String::new(),
+            system_prompt: String::new(),
+            output_structure: "# [Descriptive Title]\n\n## Problem\n\n## Environment\n\n## Cause\n\n## Resolution\n\n## Expected Result".to_string(),
+            field_mapping: Default::default(),
+            generation: Default::default(),
+            model: None,
+            is_builtin: true,
+            created_at: "2024-01-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_conforming_draft() {
+        let draft = "# VPN drops\n\n## Problem\nx\n\n## Environment\nx\n\n## Cause\nx\n\n### Details\nx\n\n## Resolution\nx\n\n## Expected Result\nx\n";
+        let report = check(draft, &troubleshooting());
+        assert_eq!(report, ConformanceReport::default());
+        assert!(warnings(&report).is_empty());
+    }
+
+    #[test]
+    fn test_reports_renamed_missing_and_extra() {
+        let draft = "# VPN drops\n\n## Symptoms\nx\n\n## Root Cause\nx\n\n## Resolution Steps\nx\n\n## Expected Results\nx\n\n## FAQ\nx\n";
+        let report = check(draft, &troubleshooting());
+
+        assert_eq!(report.missing, vec!["Environment"]);
+        assert_eq!(report.extra, vec!["FAQ"]);
+        assert!(report.out_of_order.is_empty());
+        let renamed: Vec<(&str, &str)> = report
+            .renamed
+            .iter()
+            .map(|r| (r.expected.as_str(), r.found.as_str()))
+            .collect();
+        assert_eq!(
+            renamed,
+            vec![
+                ("Problem", "Symptoms"),
+                ("Cause", "Root Cause"),
+                ("Resolution", "Resolution Steps"),
+                ("Expected Result", "Expected Results"),
+            ]
+        );
+        assert_eq!(
+            warnings(&report)[..2],
+            [
+                "Missing template section: Environment".to_string(),
+                "Section \"Symptoms\" should be named \"Problem\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_out_of_order_sections() {
+        let draft = "# T\n\n## Problem\nx\n\n## Resolution\nx\n\n## Environment\nx\n\n## Cause\nx\n\n## Expected Result\nx\n";
+        let report = check(draft, &troubleshooting());
+        assert_eq!(report.out_of_order, vec!["Resolution"]);
+        assert!(report.missing.is_empty());
+    }
+
+    #[test]
+    fn test_insert_missing_sections() {
+        let draft = "# VPN drops\n\n## Problem\nTunnel resets.\n\n## Resolution\n1. Restart\n";
+        let fixed = insert_missing(draft, &troubleshooting());
+        assert_eq!(
+            fixed,
+            "# VPN drops\n\n## Problem\nTunnel resets.\n\n## Environment\n\n[Not available in ticket - please add]\n\n## Cause\n\n[Not available in ticket - please add]\n\n## Resolution\n1. Restart\n\n## Expected Result\n\n[Not available in ticket - please add]\n"
+        );
+        assert_eq!(
+            check(&fixed, &troubleshooting()),
+            ConformanceReport::default()
+        );
+    }
+
+    #[test]
+    fn test_insert_into_draft_without_sections() {
+        let fixed = insert_missing("# VPN drops", &troubleshooting());
+        assert!(fixed.starts_with("# VPN drops\n\n## Problem\n\n"));
+        assert!(check(&fixed, &troubleshooting()).missing.is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::AppError;
+use crate::models::JiraComment;
+use crate::services::llm::LlmProvider;
+
+/// Tokens kept free for the model's answer. A KB article of 200-500 words
+/// fits comfortably; small context windows give up at most a quarter.
+const RESERVED_OUTPUT_TOKENS: usize = 1024;
+
+/// Allowance for the instructions wrapped around comments in a summarization call
+const SUMMARY_PROMPT_OVERHEAD: usize = 200;
+
+/// How many times summaries are summarized again before falling back to truncation
+const MAX_REDUCE_ROUNDS: usize = 3;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You summarize Jira support ticket comments for a \
+technical writer. Keep concrete facts: error messages, commands, versions, config values, \
+what was tried and what worked. Drop greetings, thanks and status pings. Do not invent anything.";
+
+/// Rough token count. There is no tokenizer for every model we might talk
+/// to, so this errs on the high side: about 3 characters per token, which
+/// over-counts English prose a bit and is close for code and logs.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(3)
+}
+
+/// Tokens available for the prompt once room for the answer is set aside
+pub fn prompt_budget(context_length: usize) -> usize {
+    context_length - RESERVED_OUTPUT_TOKENS.min(context_length / 4)
+}
+
+/// Render a comment the way it appears in the drafting prompt
+pub fn format_comment(comment: &JiraComment) -> String {
+    format!(
+        "[{} - {}]: {}",
+        comment.author, comment.created, comment.body
+    )
+}
+
+/// Split comments into older ones to summarize and the most recent ones to
+/// keep verbatim. Recent comments get at most half of `budget`, the rest is
+/// left for the summary. The last comment is always kept verbatim, since it
+/// usually holds the resolution.
+pub fn split_comments(comments: &[JiraComment], budget: usize) -> (&[JiraComment], &[JiraComment]) {
+    let verbatim_budget = budget / 2;
+    let mut used = 0;
+    let mut split = comments.len();
+
+    for (i, comment) in comments.iter().enumerate().rev() {
+        let tokens = estimate_tokens(&format_comment(comment));
+        if split < comments.len() && used + tokens > verbatim_budget {
+            break;
+        }
+        used += tokens;
+        split = i;
+    }
+
+    comments.split_at(split)
+}
+
+/// Group texts into chunks that each fit into `max_tokens`. A single text
+/// that is larger on its own gets a chunk to itself.
+pub fn chunk_by_tokens(texts: &[String], max_tokens: usize) -> Vec<Vec<String>> {
+    let mut chunks: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut current_tokens = 0;
+
+    for text in texts {
+        let tokens = estimate_tokens(text);
+        if !current.is_empty() && current_tokens + tokens > max_tokens {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        current.push(text.clone());
+        current_tokens += tokens;
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Cut `text` down to roughly `max_tokens`, marking the cut
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let keep: String = text.chars().take(max_tokens.saturating_mul(3)).collect();
+    format!(
+        "{}\n[... truncated to fit the model context ...]",
+        keep.trim_end()
+    )
+}
+
+/// Summarize comments into at most `budget` tokens with a map-reduce pass:
+/// each chunk that fits in one model call is summarized on its own, then the
+/// summaries are combined and summarized again until they fit.
+pub async fn summarize_comments(
+    comments: &[JiraComment],
+    budget: usize,
+    provider: &dyn LlmProvider,
+) -> Result<String, AppError> {
+    let chunk_tokens =
+        prompt_budget(provider.context_length()).saturating_sub(SUMMARY_PROMPT_OVERHEAD);
+    let mut texts: Vec<String> = comments.iter().map(format_comment).collect();
+
+    for round in 0..MAX_REDUCE_ROUNDS {
+        let chunks = chunk_by_tokens(&texts, chunk_tokens);
+        // Share the budget between the chunks so the combined result fits
+        let tokens_per_chunk = (budget / chunks.len()).max(30);
+        let words_per_chunk = tokens_per_chunk * 2 / 3;
+
+        log::info!(
+            "Summarizing {} comment chunk(s), round {}",
+            chunks.len(),
+            round + 1
+        );
+
+        let mut summaries = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            let prompt = format!(
+                "Summarize these ticket comments in at most {} words, in chronological order:\n\n{}",
+                words_per_chunk,
+                chunk.join("\n\n")
+            );
+            // Not bound by the template's `num_predict` or `stop`, which are
+            // meant for the article; room to overshoot, as the result is
+            // summarized again or truncated when it does not fit
+            let summary = provider
+                .generate_bounded(
+                    SUMMARY_SYSTEM_PROMPT,
+                    &prompt,
+                    tokens_per_chunk * 2,
+                    &mut |_| {},
+                )
+                .await?;
+            summaries.push(summary.trim().to_string());
+        }
+
+        let combined = summaries.join("\n\n");
+        if estimate_tokens(&combined) <= budget || chunks.len() == 1 && round > 0 {
+            return Ok(truncate_to_tokens(&combined, budget));
+        }
+        texts = summaries;
+    }
+
+    Ok(truncate_to_tokens(&texts.join("\n\n"), budget))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm::test_provider::FixedProvider;
+
+    fn comment(i: usize, body: &str) -> JiraComment {
+        JiraComment {
+            author: format!("user{}", i),
+            body: body.to_string(),
+            created: "2024-01-01T10:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcd"), 2);
+    }
+
+    #[test]
+    fn test_prompt_budget_reserves_output() {
+        assert_eq!(prompt_budget(4096), 3072);
+        assert_eq!(prompt_budget(2048), 1536);
+        assert_eq!(prompt_budget(32768), 31744);
+    }
+
+    #[test]
+    fn test_split_comments_keeps_recent_verbatim() {
+        let comments: Vec<_> = (0..10).map(|i| comment(i, &"x".repeat(60))).collect();
+        let per_comment = estimate_tokens(&format_comment(&comments[0]));
+
+        let (older, recent) = split_comments(&comments, per_comment * 6);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(older.len(), 7);
+        assert_eq!(recent.last().unwrap().author, "user9");
+    }
+
+    #[test]
+    fn test_split_comments_always_keeps_last() {
+        let comments = vec![comment(0, "short"), comment(1, &"long ".repeat(500))];
+        let (older, recent) = split_comments(&comments, 10);
+        assert_eq!(older.len(), 1);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].author, "user1");
+    }
+
+    #[test]
+    fn test_chunk_by_tokens() {
+        let texts: Vec<String> = vec!["a".repeat(30), "b".repeat(30), "c".repeat(30)];
+        let chunks = chunk_by_tokens(&texts, 20);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), 2);
+        assert_eq!(chunks[1].len(), 1);
+    }
+
+    #[test]
+    fn test_truncate_to_tokens() {
+        let text = "word ".repeat(100);
+        let truncated = truncate_to_tokens(&text, 10);
+        assert!(truncated.starts_with("word word"));
+        assert!(truncated.contains("truncated"));
+        assert_eq!(truncate_to_tokens("short", 10), "short");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_comments_maps_over_chunks() {
+        let provider = FixedProvider::new(1000, "Summary of earlier comments.");
+        // ~1000 tokens of comments against a 1000-token context forces several chunks
+        let comments: Vec<_> = (0..30).map(|i| comment(i, &"y".repeat(90))).collect();
+
+        let summary = summarize_comments(&comments, 200, &provider).await.unwrap();
+
+        let prompts = provider.prompts.lock().unwrap();
+        assert!(prompts.len() > 1);
+        assert!(prompts[0].contains("user0"));
+        assert!(summary.contains("Summary of earlier comments."));
+        assert!(estimate_tokens(&summary) <= 200);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::models::{Article, JiraTicket};
+
+/// Ollama embedding model used when the caller does not pick one
+pub const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+/// Matches returned when the caller does not ask for a number
+pub const DEFAULT_MATCH_LIMIT: usize = 5;
+
+/// Articles embedded per `/api/embed` request
+pub const EMBED_BATCH_SIZE: usize = 16;
+
+/// Cosine similarity below which an article is not worth showing. Unrelated
+/// support texts still score around 0.4-0.5 with most embedding models.
+pub const MIN_SIMILARITY: f32 = 0.6;
+
+/// Text embedded per article or ticket set. Embedding models have small
+/// context windows and the opening of an article says what it is about.
+const MAX_EMBED_CHARS: usize = 6000;
+
+/// The text of an article that is embedded
+pub fn article_text(article: &Article) -> String {
+    truncate(&format!(
+        "{}\n\n{}",
+        article.title, article.content_markdown
+    ))
+}
+
+/// The text of the tickets about to be drafted, comparable to `article_text`
+pub fn ticket_text(tickets: &[JiraTicket]) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for ticket in tickets {
+        parts.push(&ticket.summary);
+        parts.extend(ticket.description.as_deref());
+        parts.extend(ticket.resolution.as_deref());
+        parts.extend(ticket.labels.iter().map(String::as_str));
+        parts.extend(ticket.components.iter().map(String::as_str));
+    }
+    truncate(&parts.join("\n\n"))
+}
+
+/// Stable hash of embedded text (64-bit FNV-1a), used to tell whether a
+/// stored embedding is still up to date
+pub fn content_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// The `limit` candidates most similar to `query` with at least
+/// `min_similarity`, best first
+pub fn rank(
+    query: &[f32],
+    candidates: &[(i64, Vec<f32>)],
+    min_similarity: f32,
+    limit: usize,
+) -> Vec<(i64, f32)> {
+    let mut ranked: Vec<(i64, f32)> = candidates
+        .iter()
+        .map(|(id, vector)| (*id, cosine_similarity(query, vector)))
+        .filter(|(_, similarity)| *similarity >= min_similarity)
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(limit);
+    ranked
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_EMBED_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert!((cosine_similarity(&[3.0, 4.0], &[0.6, 0.8]) - 1.0).abs() < 1e-6);
+        // Vectors of different models or empty ones never match
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0, 0.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_rank_filters_and_orders() {
+        let candidates = vec![
+            (1, vec![0.0, 1.0]),
+            (2, vec![0.8, 0.6]),
+            (3, vec![1.0, 0.0]),
+            (4, vec![0.6, 0.8]),
+        ];
+        let ranked = rank(&[1.0, 0.0], &candidates, 0.5, 10);
+        let ids: Vec<i64> = ranked.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![3, 2, 4]);
+        assert!((ranked[1].1 - 0.8).abs() < 1e-6);
+
+        assert_eq!(rank(&[1.0, 0.0], &candidates, 0.5, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("VPN drops"), content_hash("VPN drop"));
+    }
+
+    #[test]
+    fn test_truncates_long_text() {
+        let text = "é".repeat(MAX_EMBED_CHARS + 10);
+        assert_eq!(truncate(&text).chars().count(), MAX_EMBED_CHARS);
+    }
+}
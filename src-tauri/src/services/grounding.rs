@@ -0,0 +1,309 @@
+use crate::models::{FlaggedSection, JiraTicket};
+use regex::Regex;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Share of a command's words that must appear in the ticket for the
+/// command to count as supported. Models rephrase "restarted the nginx
+/// service" as `systemctl restart nginx`, so an exact match is too strict.
+const COMMAND_SUPPORT_RATIO: f32 = 0.8;
+
+/// Placeholder the templates tell the model to use for missing information
+const MISSING_PLACEHOLDER: &str = "[Not available in ticket - please add]";
+
+/// Artifact patterns checked outside code blocks, most specific first.
+/// Overlapping matches are only checked once, by the first pattern.
+const ARTIFACT_PATTERNS: &[(&str, &str, &str)] = &[
+    (r#"https?://[^\s)>\]"'`]+"#, "ungrounded_url", "high"),
+    (r"`([^`\n]+)`", "ungrounded_command", "high"),
+    (
+        r#"(?:[A-Za-z]:\\|~/|/)[\w.\-]+(?:[/\\][\w.\-]+)+"#,
+        "ungrounded_path",
+        "medium",
+    ),
+    (
+        r"\b[A-Za-z_][\w.\-]*\s*=\s*[^\s,;]+",
+        "ungrounded_config",
+        "medium",
+    ),
+    (
+        r"\b(?:0x[0-9A-Fa-f]{4,}|[A-Z]{2,}(?:_[A-Z]+)*[_-]?\d{3,}|(?:error|code|status|HTTP)\s+\d{3,})\b",
+        "ungrounded_error_code",
+        "medium",
+    ),
+    (
+        r"\bv?\d+\.\d+(?:\.\d+){0,2}\b",
+        "ungrounded_version",
+        "medium",
+    ),
+];
+
+/// Flag concrete artifacts in a draft (commands, file paths, URLs, versions,
+/// error codes, config values) that cannot be found in any of the source
+/// tickets. These are the details a model is most likely to invent and a
+/// reader most likely to copy verbatim.
+pub fn check(markdown: &str, tickets: &[JiraTicket]) -> Vec<FlaggedSection> {
+    let source = SourceText::new(tickets);
+    let patterns: Vec<(Regex, &str, &str)> = ARTIFACT_PATTERNS
+        .iter()
+        .map(|(pattern, kind, severity)| (Regex::new(pattern).unwrap(), *kind, *severity))
+        .collect();
+
+    let mut flags = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_num, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.contains(MISSING_PLACEHOLDER) {
+            continue;
+        }
+
+        // Every line of a code block is a command or config line on its own
+        if in_code_block {
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                continue;
+            }
+            if !source.supports_command(trimmed) {
+                let start = line.len() - line.trim_start().len();
+                flags.push(flag(
+                    "ungrounded_command",
+                    "high",
+                    trimmed,
+                    line_num,
+                    start..start + trimmed.len(),
+                ));
+            }
+            continue;
+        }
+
+        let mut checked: Vec<Range<usize>> = Vec::new();
+        for (re, kind, severity) in &patterns {
+            for caps in re.captures_iter(line) {
+                let whole = caps.get(0).unwrap();
+                if checked
+                    .iter()
+                    .any(|r| r.start < whole.end() && whole.start() < r.end)
+                {
+                    continue;
+                }
+                checked.push(whole.range());
+
+                // Inline code is checked without its backticks
+                let artifact = caps.get(1).unwrap_or(whole);
+                let text = artifact
+                    .as_str()
+                    .trim_end_matches(['.', ',', ';', ':', ')']);
+                let supported = if *kind == "ungrounded_command" {
+                    source.supports_command(text)
+                } else {
+                    source.contains(text)
+                };
+
+                if !supported {
+                    flags.push(flag(
+                        kind,
+                        severity,
+                        text,
+                        line_num,
+                        artifact.start()..artifact.start() + text.len(),
+                    ));
+                }
+            }
+        }
+    }
+
+    flags.sort_by_key(|f| (f.line_number, f.start_col));
+    flags
+}
+
+fn flag(
+    kind: &str,
+    severity: &str,
+    text: &str,
+    line_num: usize,
+    cols: Range<usize>,
+) -> FlaggedSection {
+    let matched_text = if text.chars().count() > 50 {
+        format!("{}...", text.chars().take(47).collect::<String>())
+    } else {
+        text.to_string()
+    };
+
+    log::debug!(
+        "Unsupported {} at line {}: {}",
+        kind,
+        line_num + 1,
+        matched_text
+    );
+
+    FlaggedSection {
+        pattern_type: kind.to_string(),
+        severity: severity.to_string(),
+        matched_text,
+        line_number: line_num + 1, // 1-indexed for user display
+        start_col: cols.start,
+        end_col: cols.end,
+    }
+}
+
+/// Everything the model was given, normalized for matching
+struct SourceText {
+    text: String,
+    words: HashSet<String>,
+}
+
+impl SourceText {
+    fn new(tickets: &[JiraTicket]) -> Self {
+        let mut parts: Vec<&str> = Vec::new();
+        for ticket in tickets {
+            parts.push(&ticket.key);
+            parts.push(&ticket.summary);
+            parts.extend(ticket.description.as_deref());
+            parts.extend(ticket.resolution.as_deref());
+            parts.extend(ticket.labels.iter().map(String::as_str));
+            parts.extend(ticket.components.iter().map(String::as_str));
+            parts.extend(ticket.comments.iter().map(|c| c.body.as_str()));
+        }
+
+        let text = normalize(&parts.join("\n"));
+        let words = words(&text).map(str::to_string).collect();
+        Self { text, words }
+    }
+
+    fn contains(&self, artifact: &str) -> bool {
+        let artifact = normalize(artifact);
+        let artifact = artifact.trim_end_matches('/');
+        artifact.is_empty() || self.text.contains(artifact)
+    }
+
+    fn supports_command(&self, command: &str) -> bool {
+        if self.contains(command) {
+            return true;
+        }
+        let tokens: Vec<&str> = words(command).collect();
+        if tokens.is_empty() {
+            return true;
+        }
+        let found = tokens
+            .iter()
+            .filter(|t| {
+                self.words.contains(&t.to_lowercase()) || self.text.contains(&t.to_lowercase())
+            })
+            .count();
+        found as f32 / tokens.len() as f32 >= COMMAND_SUPPORT_RATIO
+    }
+}
+
+/// Lowercase and collapse whitespace, so line wrapping and case do not matter
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Words worth checking: short filler like "-f" or "a" says nothing
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '(' | ')' | ',' | ';'))
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| w.len() >= 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::JiraComment;
+
+    fn ticket() -> JiraTicket {
+        JiraTicket {
+            key: "OPS-42".to_string(),
+            summary: "nginx returns 502 after upgrade to 1.25.3".to_string(),
+            description: Some(
+                "After the upgrade, /etc/nginx/conf.d/upstream.conf still points at port 8080. \
+                 Error E_UPSTREAM_502 in the logs. Docs: https://nginx.org/en/docs/"
+                    .to_string(),
+            ),
+            status: "Resolved".to_string(),
+            priority: None,
+            resolution: Some("Fixed".to_string()),
+            labels: vec![],
+            components: vec![],
+            comments: vec![JiraComment {
+                author: "Alice".to_string(),
+                body: "Set proxy_read_timeout=120 and restarted nginx with systemctl".to_string(),
+                created: "2024-01-01T10:00:00".to_string(),
+            }],
+            linked_issues: vec![],
+            timeline: vec![],
+            created: "2024-01-01T09:00:00".to_string(),
+            updated: "2024-01-01T12:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_grounded_draft_has_no_flags() {
+        let draft = "# nginx 502 after upgrade\n\n## Cause\nVersion 1.25.3 reads `/etc/nginx/conf.d/upstream.conf`.\n\n## Resolution\n1. Set proxy_read_timeout=120\n2. Restart nginx:\n```bash\nsystemctl restart nginx\n```\nSee https://nginx.org/en/docs/.\n";
+        let flags = check(draft, &[ticket()]);
+        assert!(flags.is_empty(), "unexpected flags: {:?}", flags);
+    }
+
+    #[test]
+    fn test_flags_invented_artifacts() {
+        let draft = "# nginx 502\n\nUpgrade to 1.27.0 and edit /etc/nginx/sites-enabled/default.\nSet keepalive_timeout=65 to fix ERR_CONN_9001.\nDocs: https://example.com/nginx-fix\n```bash\nsudo apt-get install nginx-extras\n```\n";
+        let flags = check(draft, &[ticket()]);
+
+        let found: Vec<(&str, &str, usize)> = flags
+            .iter()
+            .map(|f| {
+                (
+                    f.pattern_type.as_str(),
+                    f.matched_text.as_str(),
+                    f.line_number,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("ungrounded_version", "1.27.0", 3),
+                ("ungrounded_path", "/etc/nginx/sites-enabled/default", 3),
+                ("ungrounded_config", "keepalive_timeout=65", 4),
+                ("ungrounded_error_code", "ERR_CONN_9001", 4),
+                ("ungrounded_url", "https://example.com/nginx-fix", 5),
+                ("ungrounded_command", "sudo apt-get install nginx-extras", 7),
+            ]
+        );
+        assert_eq!(flags[0].start_col, "Upgrade to ".len());
+        assert_eq!(flags[0].end_col, "Upgrade to 1.27.0".len());
+    }
+
+    #[test]
+    fn test_inline_code_is_checked_as_command() {
+        let flags = check("Run `nginx -s reload` now.", &[ticket()]);
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].pattern_type, "ungrounded_command");
+        assert_eq!(flags[0].matched_text, "nginx -s reload");
+        assert_eq!(flags[0].start_col, 5);
+    }
+
+    #[test]
+    fn test_checks_every_source_ticket() {
+        let mut other = ticket();
+        other.key = "OPS-43".to_string();
+        other.description = Some("Same on 1.27.0".to_string());
+        other.comments = vec![];
+
+        assert!(!check("Also seen on 1.27.0.", &[ticket()]).is_empty());
+        assert!(check("Also seen on 1.27.0.", &[ticket(), other]).is_empty());
+    }
+
+    #[test]
+    fn test_ignores_placeholders_and_numbered_lists() {
+        let draft = "## Environment\n[Not available in ticket - please add]\n\n1. Open the settings\n2. Save";
+        assert!(check(draft, &[ticket()]).is_empty());
+    }
+}
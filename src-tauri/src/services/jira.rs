@@ -1,40 +1,37 @@
 use crate::error::AppError;
-use crate::models::{JiraComment, JiraTicket};
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use crate::models::{Credentials, JiraComment, JiraIssueLink, JiraTicket, TimelineEvent};
+use crate::services::atlassian::Authorize;
+use crate::services::{adf_to_markdown, timeline};
 use serde_json::Value;
 
+/// Issues requested per page when paging through search results
+const SEARCH_PAGE_SIZE: usize = 50;
+
+/// Change histories requested per page from the changelog endpoint, which
+/// is also the most `expand=changelog` returns
+const CHANGELOG_PAGE_SIZE: usize = 100;
+
 pub struct JiraClient {
     base_url: String,
-    pat: String,
+    credentials: Credentials,
     client: reqwest::Client,
 }
 
 impl JiraClient {
-    pub fn new(base_url: String, pat: String) -> Self {
+    pub fn new(base_url: String, credentials: Credentials) -> Self {
         Self {
             base_url,
-            pat,
+            credentials,
             client: reqwest::Client::new(),
         }
     }
 
-    fn headers(&self) -> Result<HeaderMap, AppError> {
-        let mut headers = HeaderMap::new();
-        let auth_value = format!("Bearer {}", self.pat);
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::from_str(&auth_value)
-                .map_err(|e| AppError::Internal(format!("Invalid auth header: {}", e)))?,
-        );
-        Ok(headers)
-    }
-
     pub async fn test_connection(&self) -> Result<bool, AppError> {
         let url = format!("{}/rest/api/2/myself", self.base_url);
         let response = self
             .client
             .get(&url)
-            .headers(self.headers()?)
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -50,16 +47,17 @@ impl JiraClient {
         }
     }
 
+    /// Fetch a ticket with its comments, links and change history
     pub async fn get_ticket(&self, key: &str) -> Result<JiraTicket, AppError> {
         let url = format!(
-            "{}/rest/api/2/issue/{}?fields=summary,description,status,priority,resolution,labels,components,comment,created,updated",
+            "{}/rest/api/2/issue/{}?fields=summary,description,status,priority,resolution,labels,components,comment,issuelinks,reporter,created,updated&expand=changelog",
             self.base_url, key
         );
 
         let response = self
             .client
             .get(&url)
-            .headers(self.headers()?)
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -86,10 +84,70 @@ impl JiraClient {
             });
         }
 
-        let json: Value = response.json().await?;
+        let mut json: Value = response.json().await?;
+        self.complete_changelog(key, &mut json).await;
         self.parse_ticket(&json)
     }
 
+    /// `expand=changelog` stops at 100 histories. Long tickets, such as the
+    /// incidents that post-mortems are written about, get the rest from the
+    /// changelog endpoint so their timeline is not cut short. If that
+    /// fails, the timeline is built from what came with the ticket.
+    async fn complete_changelog(&self, key: &str, json: &mut Value) {
+        let Some(mut histories) = missing_histories(&json["changelog"]) else {
+            return;
+        };
+        let total = json["changelog"]["total"].as_u64().unwrap_or(0) as usize;
+
+        let mut start_at = 0;
+        while start_at < total {
+            match self.get_changelog_page(key, start_at).await {
+                Ok(page) if !page.is_empty() => {
+                    start_at += page.len();
+                    merge_histories(&mut histories, page);
+                }
+                Ok(_) => break,
+                Err(e) => {
+                    log::warn!(
+                        "Timeline of {} misses {} of {} changes: {}",
+                        key,
+                        total.saturating_sub(histories.len()),
+                        total,
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+
+        json["changelog"]["histories"] = Value::Array(histories);
+    }
+
+    async fn get_changelog_page(&self, key: &str, start_at: usize) -> Result<Vec<Value>, AppError> {
+        let url = format!(
+            "{}/rest/api/2/issue/{}/changelog?startAt={}&maxResults={}",
+            self.base_url, key, start_at, CHANGELOG_PAGE_SIZE
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .authorize(&self.credentials)
+            .send()
+            .await?;
+
+        let status_code = response.status().as_u16();
+        if status_code != 200 {
+            return Err(AppError::Jira {
+                status: status_code,
+                message: "Failed to fetch changelog".to_string(),
+            });
+        }
+
+        let json: Value = response.json().await?;
+        Ok(json["values"].as_array().cloned().unwrap_or_default())
+    }
+
     pub async fn search_tickets(&self, query: &str) -> Result<Vec<JiraTicket>, AppError> {
         // Validate input to prevent JQL injection
         if query.contains('"') || query.contains('\'') || query.contains('\\') {
@@ -108,7 +166,7 @@ impl JiraClient {
         let response = self
             .client
             .get(&url)
-            .headers(self.headers()?)
+            .authorize(&self.credentials)
             .send()
             .await?;
 
@@ -135,6 +193,67 @@ impl JiraClient {
         Ok(tickets)
     }
 
+    /// Keys of every issue matching `jql`, up to `limit`, following the
+    /// search pagination, along with the total number of matches. Unlike
+    /// `search_tickets` the query is used as is.
+    pub async fn search_keys(
+        &self,
+        jql: &str,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), AppError> {
+        let mut keys = Vec::new();
+        let mut total = 0;
+
+        while keys.len() < limit {
+            let page_size = (limit - keys.len()).min(SEARCH_PAGE_SIZE);
+            let url = format!(
+                "{}/rest/api/2/search?jql={}&startAt={}&maxResults={}&fields=key",
+                self.base_url,
+                urlencoding::encode(jql),
+                keys.len(),
+                page_size
+            );
+
+            let response = self
+                .client
+                .get(&url)
+                .authorize(&self.credentials)
+                .send()
+                .await?;
+
+            let status_code = response.status().as_u16();
+            if status_code == 400 {
+                // Jira explains what is wrong with the query
+                let json: Value = response.json().await.unwrap_or_default();
+                let message = json["errorMessages"][0]
+                    .as_str()
+                    .unwrap_or("Invalid JQL query")
+                    .to_string();
+                return Err(AppError::Jira { status: 400, message });
+            }
+            if status_code != 200 {
+                return Err(AppError::Jira {
+                    status: status_code,
+                    message: "Search failed".to_string(),
+                });
+            }
+
+            let json: Value = response.json().await?;
+            let page = parse_issue_keys(&json)?;
+            total = json["total"].as_u64().unwrap_or(0) as usize;
+            let exhausted = page.is_empty();
+            keys.extend(page);
+
+            if exhausted || keys.len() >= total {
+                break;
+            }
+        }
+
+        keys.truncate(limit);
+        let total = total.max(keys.len());
+        Ok((keys, total))
+    }
+
     fn parse_ticket(&self, json: &Value) -> Result<JiraTicket, AppError> {
         let key = json["key"]
             .as_str()
@@ -148,7 +267,8 @@ impl JiraClient {
             .ok_or_else(|| AppError::Internal("Missing summary".to_string()))?
             .to_string();
 
-        let description = fields["description"].as_str().map(|s| s.to_string());
+        // A string from API v2, an ADF document from v3 and Jira Cloud
+        let description = adf_to_markdown::text(&fields["description"]);
 
         let status = fields["status"]["name"]
             .as_str()
@@ -183,6 +303,8 @@ impl JiraClient {
 
         let comments = self.parse_comments(&fields["comment"]);
 
+        let linked_issues = self.parse_issue_links(&fields["issuelinks"]);
+
         let created = fields["created"]
             .as_str()
             .unwrap_or("")
@@ -193,6 +315,12 @@ impl JiraClient {
             .unwrap_or("")
             .to_string();
 
+        let reporter = fields["reporter"]["displayName"]
+            .as_str()
+            .unwrap_or("Unknown");
+        let changes = self.parse_changelog(&json["changelog"]);
+        let timeline = timeline::build(&created, reporter, changes, &comments);
+
         Ok(JiraTicket {
             key,
             summary,
@@ -203,6 +331,8 @@ impl JiraClient {
             labels,
             components,
             comments,
+            linked_issues,
+            timeline,
             created,
             updated,
         })
@@ -220,7 +350,7 @@ impl JiraClient {
                 let author = comment["author"]["displayName"]
                     .as_str()?
                     .to_string();
-                let body = comment["body"].as_str()?.to_string();
+                let body = adf_to_markdown::text(&comment["body"])?;
                 let created = comment["created"].as_str()?.to_string();
 
                 Some(JiraComment {
@@ -231,15 +361,112 @@ impl JiraClient {
             })
             .collect()
     }
+
+    /// Status, assignee, priority and resolution changes from the history
+    /// returned with `expand=changelog`
+    fn parse_changelog(&self, changelog: &Value) -> Vec<TimelineEvent> {
+        let histories = match changelog["histories"].as_array() {
+            Some(arr) => arr,
+            None => return Vec::new(),
+        };
+
+        histories
+            .iter()
+            .flat_map(|history| {
+                let author = history["author"]["displayName"]
+                    .as_str()
+                    .unwrap_or("Unknown");
+                let timestamp = history["created"].as_str().unwrap_or("");
+                let items = history["items"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+
+                items.iter().filter_map(move |item| {
+                    let kind = timeline::field_kind(item["field"].as_str()?)?;
+                    Some(TimelineEvent {
+                        timestamp: timestamp.to_string(),
+                        author: author.to_string(),
+                        kind,
+                        from: item["fromString"].as_str().map(|s| s.to_string()),
+                        to: item["toString"].as_str().map(|s| s.to_string()),
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Each link names the other issue as either `inwardIssue` or
+    /// `outwardIssue`, with the matching description on the link type
+    fn parse_issue_links(&self, links: &Value) -> Vec<JiraIssueLink> {
+        let links = match links.as_array() {
+            Some(arr) => arr,
+            None => return Vec::new(),
+        };
+
+        links
+            .iter()
+            .filter_map(|link| {
+                let (issue, relationship) = if link["outwardIssue"].is_object() {
+                    (&link["outwardIssue"], &link["type"]["outward"])
+                } else {
+                    (&link["inwardIssue"], &link["type"]["inward"])
+                };
+
+                Some(JiraIssueLink {
+                    key: issue["key"].as_str()?.to_string(),
+                    relationship: relationship
+                        .as_str()
+                        .or_else(|| link["type"]["name"].as_str())
+                        .unwrap_or("relates to")
+                        .to_string(),
+                    summary: issue["fields"]["summary"]
+                        .as_str()
+                        .unwrap_or("")
+                        .to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_issue_keys(json: &Value) -> Result<Vec<String>, AppError> {
+    let issues = json["issues"].as_array().ok_or_else(|| {
+        AppError::Internal("Invalid search response: missing issues array".to_string())
+    })?;
+
+    Ok(issues
+        .iter()
+        .filter_map(|issue| issue["key"].as_str().map(|s| s.to_string()))
+        .collect())
+}
+
+/// The histories that came with `changelog` when Jira left some out
+fn missing_histories(changelog: &Value) -> Option<Vec<Value>> {
+    let histories = changelog["histories"].as_array()?;
+    let total = changelog["total"].as_u64().unwrap_or(0) as usize;
+    (total > histories.len()).then(|| histories.clone())
+}
+
+/// Add the histories of `page` that are not in `histories` yet, by id
+fn merge_histories(histories: &mut Vec<Value>, page: Vec<Value>) {
+    for history in page {
+        if !histories.iter().any(|h| h["id"] == history["id"]) {
+            histories.push(history);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::TimelineEventKind;
 
     #[test]
     fn test_parse_ticket() {
-        let client = JiraClient::new("http://test".to_string(), "token".to_string());
+        let client = JiraClient::new(
+            "http://test".to_string(),
+            Credentials::Pat {
+                token: "token".to_string(),
+            },
+        );
 
         let json = serde_json::json!({
             "key": "TEST-123",
@@ -258,6 +485,13 @@ mod tests {
                         "created": "2024-01-01T00:00:00.000Z"
                     }]
                 },
+                "issuelinks": [{
+                    "type": { "name": "Duplicate", "inward": "is duplicated by", "outward": "duplicates" },
+                    "inwardIssue": { "key": "TEST-99", "fields": { "summary": "Same crash" } }
+                }, {
+                    "type": { "name": "Relates", "inward": "relates to", "outward": "relates to" },
+                    "outwardIssue": { "key": "TEST-7", "fields": { "summary": "Older report" } }
+                }],
                 "created": "2024-01-01T00:00:00.000Z",
                 "updated": "2024-01-02T00:00:00.000Z"
             }
@@ -270,5 +504,150 @@ mod tests {
         assert_eq!(ticket.labels, vec!["bug", "urgent"]);
         assert_eq!(ticket.comments.len(), 1);
         assert_eq!(ticket.comments[0].author, "John Doe");
+        assert_eq!(ticket.linked_issues.len(), 2);
+        assert_eq!(ticket.linked_issues[0].key, "TEST-99");
+        assert_eq!(ticket.linked_issues[0].relationship, "is duplicated by");
+        assert_eq!(ticket.linked_issues[1].key, "TEST-7");
+        assert_eq!(ticket.linked_issues[1].summary, "Older report");
+    }
+
+    #[test]
+    fn test_parse_ticket_adf_fields() {
+        let client = JiraClient::new(
+            "http://test".to_string(),
+            Credentials::Pat {
+                token: "token".to_string(),
+            },
+        );
+
+        let adf = |text: &str| {
+            serde_json::json!({
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "paragraph",
+                    "content": [{ "type": "text", "text": text }]
+                }]
+            })
+        };
+        let json = serde_json::json!({
+            "key": "CLOUD-7",
+            "fields": {
+                "summary": "Outlook keeps asking for a password",
+                "description": adf("Started after the MFA rollout"),
+                "status": { "name": "Resolved" },
+                "comment": {
+                    "comments": [{
+                        "author": { "displayName": "Sam" },
+                        "body": adf("Cleared the cached credentials"),
+                        "created": "2024-03-01T09:20:00.000+0000"
+                    }]
+                },
+                "created": "2024-03-01T08:45:00.000+0000",
+                "updated": "2024-03-01T10:00:00.000+0000"
+            }
+        });
+
+        let ticket = client.parse_ticket(&json).unwrap();
+        assert_eq!(
+            ticket.description.as_deref(),
+            Some("Started after the MFA rollout")
+        );
+        assert_eq!(ticket.comments.len(), 1);
+        assert_eq!(ticket.comments[0].body, "Cleared the cached credentials");
+    }
+
+    #[test]
+    fn test_parse_ticket_timeline() {
+        let client = JiraClient::new(
+            "http://test".to_string(),
+            Credentials::Pat {
+                token: "token".to_string(),
+            },
+        );
+
+        let json = serde_json::json!({
+            "key": "OPS-42",
+            "fields": {
+                "summary": "Mail relay down",
+                "status": { "name": "Resolved" },
+                "reporter": { "displayName": "Alex" },
+                "comment": {
+                    "comments": [{
+                        "author": { "displayName": "Sam" },
+                        "body": "Relay restarted, queue draining",
+                        "created": "2024-03-01T09:20:00.000+0000"
+                    }]
+                },
+                "created": "2024-03-01T08:45:00.000+0000",
+                "updated": "2024-03-01T10:00:00.000+0000"
+            },
+            "changelog": {
+                "histories": [{
+                    "author": { "displayName": "Dana" },
+                    "created": "2024-03-01T08:50:00.000+0000",
+                    "items": [
+                        { "field": "priority", "fromString": "Medium", "toString": "Critical" },
+                        { "field": "labels", "fromString": "", "toString": "mail" },
+                        { "field": "assignee", "fromString": null, "toString": "Sam" }
+                    ]
+                }, {
+                    "author": { "displayName": "Sam" },
+                    "created": "2024-03-01T09:30:00.000+0000",
+                    "items": [
+                        { "field": "status", "fromString": "In Progress", "toString": "Resolved" }
+                    ]
+                }]
+            }
+        });
+
+        let ticket = client.parse_ticket(&json).unwrap();
+        let events: Vec<(TimelineEventKind, &str)> = ticket
+            .timeline
+            .iter()
+            .map(|e| (e.kind, e.author.as_str()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (TimelineEventKind::Created, "Alex"),
+                (TimelineEventKind::Priority, "Dana"),
+                (TimelineEventKind::Assignee, "Dana"),
+                (TimelineEventKind::Comment, "Sam"),
+                (TimelineEventKind::Status, "Sam"),
+            ]
+        );
+        assert_eq!(ticket.timeline[1].from.as_deref(), Some("Medium"));
+        assert_eq!(ticket.timeline[1].to.as_deref(), Some("Critical"));
+        assert_eq!(ticket.timeline[2].from, None);
+    }
+
+    #[test]
+    fn test_complete_changelog_merges_pages() {
+        let history = |id: &str| serde_json::json!({ "id": id, "items": [] });
+        let complete = serde_json::json!({ "total": 1, "histories": [history("1")] });
+        assert!(missing_histories(&complete).is_none());
+
+        let truncated = serde_json::json!({
+            "maxResults": 2,
+            "total": 3,
+            "histories": [history("1"), history("2")]
+        });
+        let mut histories = missing_histories(&truncated).unwrap();
+        merge_histories(&mut histories, vec![history("2"), history("3")]);
+        let ids: Vec<&str> = histories.iter().map(|h| h["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_parse_issue_keys() {
+        let json = serde_json::json!({
+            "startAt": 0,
+            "maxResults": 50,
+            "total": 2,
+            "issues": [{ "key": "SUP-1" }, { "key": "SUP-2" }]
+        });
+        assert_eq!(parse_issue_keys(&json).unwrap(), vec!["SUP-1", "SUP-2"]);
+        assert!(parse_issue_keys(&serde_json::json!({})).is_err());
     }
 }
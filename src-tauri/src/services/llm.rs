@@ -0,0 +1,429 @@
+use crate::error::AppError;
+use crate::models::{GenerationSettings, LlmProviderKind};
+use crate::services::{ollama::OllamaProvider, openai_compat::OpenAiCompatProvider};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long to wait for the first token. Prompt evaluation of a long ticket
+/// on a CPU-only box can easily take minutes before anything is streamed.
+const FIRST_TOKEN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How long to wait between two consecutive chunks once generation started
+const CHUNK_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Context window assumed when none is configured. This matches Ollama's
+/// default `num_ctx`.
+pub const DEFAULT_CONTEXT_LENGTH: usize = 4096;
+
+/// Callback receiving partial output while a response streams in
+pub type TokenCallback<'a> = &'a mut (dyn FnMut(&str) + Send);
+
+/// A single turn of a chat conversation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+/// Token counts a backend reported for a completion
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub prompt_eval_count: Option<u64>,
+    pub eval_count: Option<u64>,
+}
+
+/// A text generation backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Name used in logs and error messages
+    fn name(&self) -> &'static str;
+
+    /// Model requests are sent to
+    fn model(&self) -> &str;
+
+    /// Sampling settings sent with requests, with `num_ctx` set to the
+    /// context length in use
+    fn generation_settings(&self) -> GenerationSettings {
+        GenerationSettings {
+            num_ctx: Some(self.context_length()),
+            ..Default::default()
+        }
+    }
+
+    /// Token counts of the last completion, for backends that report them
+    fn last_usage(&self) -> Option<TokenUsage> {
+        None
+    }
+
+    /// Context window, in tokens, that prompts have to fit into
+    fn context_length(&self) -> usize;
+
+    /// Check whether the backend is reachable
+    async fn check_health(&self) -> Result<bool, AppError>;
+
+    /// Run a chat completion, streaming partial output to `on_token`.
+    /// Returns the full concatenated output.
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError>;
+
+    /// Generate a completion from a system and a user prompt
+    async fn generate(
+        &self,
+        system: &str,
+        prompt: &str,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(prompt)];
+        self.chat(&messages, on_token).await
+    }
+
+    /// Generate at most `max_tokens`, without the `stop` sequences and
+    /// `num_predict` of the sampling settings. Those are meant for the
+    /// article; helper calls such as comment summaries set their own length.
+    async fn generate_bounded(
+        &self,
+        system: &str,
+        prompt: &str,
+        _max_tokens: usize,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        self.generate(system, prompt, on_token).await
+    }
+
+    /// Whether `generate_structured` constrains the output to the schema
+    fn supports_structured_output(&self) -> bool {
+        false
+    }
+
+    /// Generate JSON matching `schema`. Backends that cannot constrain their
+    /// output fall back to a plain completion, so callers must validate it.
+    async fn generate_structured(
+        &self,
+        system: &str,
+        prompt: &str,
+        _schema: &serde_json::Value,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        self.generate(system, prompt, on_token).await
+    }
+}
+
+/// Build the provider selected in the user's settings
+pub fn provider_for(
+    kind: LlmProviderKind,
+    base_url: &str,
+    model: &str,
+    api_key: Option<String>,
+    context_length: Option<usize>,
+    generation: &GenerationSettings,
+) -> Box<dyn LlmProvider> {
+    match kind {
+        LlmProviderKind::Ollama => Box::new(
+            OllamaProvider::new(base_url, model)
+                .with_context_length(context_length)
+                .with_generation_settings(generation),
+        ),
+        LlmProviderKind::OpenaiCompatible => Box::new(
+            OpenAiCompatProvider::new(base_url, model, api_key)
+                .with_context_length(context_length)
+                .with_generation_settings(generation),
+        ),
+    }
+}
+
+/// Read a streamed response line by line, handing each non-empty line to
+/// `on_line` until it reports the stream is done.
+///
+/// There is no overall deadline: the request only fails if the backend stays
+/// silent for too long. Dropping the returned future aborts the request.
+pub async fn read_lines<F>(mut response: reqwest::Response, mut on_line: F) -> Result<(), AppError>
+where
+    F: FnMut(&str) -> Result<bool, AppError>,
+{
+    let mut decoder = LineDecoder::default();
+    let mut idle_timeout = FIRST_TOKEN_TIMEOUT;
+
+    loop {
+        let chunk = tokio::time::timeout(idle_timeout, response.chunk())
+            .await
+            .map_err(|_| {
+                AppError::Internal(format!(
+                    "Model stopped responding (no output for {} seconds)",
+                    idle_timeout.as_secs()
+                ))
+            })??;
+
+        let (lines, eof) = match chunk {
+            Some(bytes) => (decoder.push(&bytes), false),
+            None => (decoder.finish(), true),
+        };
+
+        for line in lines {
+            if on_line(&line)? {
+                return Ok(());
+            }
+        }
+
+        if eof {
+            break;
+        }
+        idle_timeout = CHUNK_IDLE_TIMEOUT;
+    }
+
+    Err(AppError::Internal(
+        "Model closed the stream before generation finished".to_string(),
+    ))
+}
+
+/// Splits a byte stream into lines. Network chunks do not line up with
+/// NDJSON records or SSE events, so partial lines are buffered until the
+/// next chunk completes them.
+#[derive(Debug, Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+}
+
+impl LineDecoder {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut lines = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    /// Flush whatever is left once the stream has ended
+    pub fn finish(&mut self) -> Vec<String> {
+        let rest = String::from_utf8_lossy(&self.buffer).trim().to_string();
+        self.buffer.clear();
+        if rest.is_empty() {
+            Vec::new()
+        } else {
+            vec![rest]
+        }
+    }
+}
+
+/// Minimal HTTP server for exercising the providers without a real backend.
+#[cfg(test)]
+pub mod test_server {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// Serve `body` with the given status and content type to every incoming
+    /// request. Returns the base URL and a handle yielding the raw requests
+    /// received once the listener is dropped.
+    pub async fn serve(
+        status: u16,
+        content_type: &'static str,
+        body: String,
+    ) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            while let Ok(Ok((mut socket, _))) =
+                tokio::time::timeout(std::time::Duration::from_millis(500), listener.accept()).await
+            {
+                requests.push(read_request(&mut socket).await);
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content_type,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+
+            let text = String::from_utf8_lossy(&data);
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let content_length = text[..header_end]
+                    .lines()
+                    .find_map(|l| {
+                        let (name, value) = l.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if data.len() >= header_end + 4 + content_length {
+                    break;
+                }
+            }
+        }
+        String::from_utf8_lossy(&data).to_string()
+    }
+}
+
+/// Provider answering every call with a fixed reply, for testing code that
+/// drives the model without going over HTTP.
+#[cfg(test)]
+pub mod test_provider {
+    use super::*;
+    use std::sync::Mutex;
+
+    pub struct FixedProvider {
+        pub context_length: usize,
+        pub reply: String,
+        /// Claim support for structured output
+        pub structured: bool,
+        /// Last message of every call, in order
+        pub prompts: Mutex<Vec<String>>,
+    }
+
+    impl FixedProvider {
+        pub fn new(context_length: usize, reply: &str) -> Self {
+            Self {
+                context_length,
+                reply: reply.to_string(),
+                structured: false,
+                prompts: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for FixedProvider {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn model(&self) -> &str {
+            "fixed-model"
+        }
+
+        fn context_length(&self) -> usize {
+            self.context_length
+        }
+
+        fn supports_structured_output(&self) -> bool {
+            self.structured
+        }
+
+        async fn check_health(&self) -> Result<bool, AppError> {
+            Ok(true)
+        }
+
+        async fn chat(
+            &self,
+            messages: &[ChatMessage],
+            on_token: TokenCallback<'_>,
+        ) -> Result<String, AppError> {
+            let prompt = messages
+                .last()
+                .map(|m| m.content.clone())
+                .unwrap_or_default();
+            self.prompts.lock().unwrap().push(prompt);
+            on_token(&self.reply);
+            Ok(self.reply.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_decoder_buffers_partial_lines() {
+        let mut decoder = LineDecoder::default();
+
+        let lines = decoder.push(b"{\"response\":\"Hel\"}\n{\"respo");
+        assert_eq!(lines, vec![r#"{"response":"Hel"}"#]);
+
+        let lines = decoder.push(b"nse\":\"lo\"}\n\n");
+        assert_eq!(lines, vec![r#"{"response":"lo"}"#]);
+
+        assert!(decoder.finish().is_empty());
+    }
+
+    #[test]
+    fn test_line_decoder_flushes_trailing_line() {
+        let mut decoder = LineDecoder::default();
+        assert!(decoder.push(br#"{"response":"","done":true}"#).is_empty());
+        assert_eq!(decoder.finish(), vec![r#"{"response":"","done":true}"#]);
+    }
+
+    #[test]
+    fn test_provider_for_selects_backend() {
+        let ollama = provider_for(
+            LlmProviderKind::Ollama,
+            "http://localhost:11434",
+            "llama3.2",
+            None,
+            Some(8192),
+            &GenerationSettings::default(),
+        );
+        assert_eq!(ollama.context_length(), 8192);
+        assert_eq!(ollama.name(), "Ollama");
+
+        let openai = provider_for(
+            LlmProviderKind::OpenaiCompatible,
+            "http://localhost:8080",
+            "qwen2.5",
+            None,
+            None,
+            &GenerationSettings::default(),
+        );
+        assert_eq!(openai.context_length(), DEFAULT_CONTEXT_LENGTH);
+
+        let template_ctx = GenerationSettings {
+            num_ctx: Some(32768),
+            ..Default::default()
+        };
+        let ollama = provider_for(
+            LlmProviderKind::Ollama,
+            "http://localhost:11434",
+            "llama3.2",
+            None,
+            Some(8192),
+            &template_ctx,
+        );
+        assert_eq!(ollama.context_length(), 32768);
+        assert_eq!(openai.name(), "OpenAI-compatible");
+    }
+}
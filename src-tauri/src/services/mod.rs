@@ -1,8 +1,26 @@
+pub mod adf_to_markdown;
+pub mod article_fields;
+pub mod atlassian;
+pub mod batch_queue;
+pub mod cleanup;
 pub mod confluence;
+pub mod conformance;
+pub mod context_budget;
 pub mod drafter;
+pub mod duplicates;
+pub mod grounding;
+pub mod injection;
 pub mod jira;
+pub mod llm;
 pub mod markdown_to_confluence;
 pub mod ollama;
+pub mod openai_compat;
 pub mod quality;
+pub mod related_articles;
+pub mod sections;
 pub mod sensitive_data;
+pub mod tag_suggester;
+pub mod template_recommender;
+pub mod timeline;
 pub mod tokens;
+pub mod translator;
@@ -1,5 +1,11 @@
 use crate::error::AppError;
+use crate::models::{GenerationSettings, ModelPullProgress, OllamaModel, OllamaModelDetails};
+use crate::services::llm::{self, ChatMessage, LlmProvider, TokenCallback, TokenUsage};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
 
 #[derive(Debug, Serialize)]
 struct GenerateRequest {
@@ -7,11 +13,79 @@ struct GenerateRequest {
     system: String,
     prompt: String,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<RequestOptions>,
+    /// JSON schema the output has to follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<RequestOptions>,
+}
+
+/// Model parameters sent with a request. Unset ones keep the model's defaults.
+#[derive(Debug, Serialize)]
+struct RequestOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+/// The `options` of a request with `settings`, or none when they are all defaults
+fn request_options(settings: &GenerationSettings) -> Option<RequestOptions> {
+    (*settings != GenerationSettings::default()).then(|| RequestOptions {
+        num_ctx: settings.num_ctx,
+        temperature: settings.temperature,
+        top_p: settings.top_p,
+        seed: settings.seed,
+        num_predict: settings.num_predict,
+        stop: settings.stop.clone(),
+    })
+}
+
+/// One line of Ollama's NDJSON stream. `/api/generate` fills `response`,
+/// `/api/chat` fills `message`.
 #[derive(Debug, Deserialize)]
-struct GenerateResponse {
+struct GenerateChunk {
+    #[serde(default)]
     response: String,
+    message: Option<ChunkMessage>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    /// Prompt and output token counts, sent with the final chunk
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkMessage {
+    #[serde(default)]
+    content: String,
+}
+
+impl GenerateChunk {
+    fn text(&self) -> &str {
+        match &self.message {
+            Some(message) => &message.content,
+            None => &self.response,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,6 +96,244 @@ struct TagsResponse {
 #[derive(Debug, Deserialize)]
 struct ModelInfo {
     name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: String,
+    #[serde(default)]
+    details: ModelDetails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ModelDetails {
+    family: Option<String>,
+    parameter_size: Option<String>,
+    quantization_level: Option<String>,
+}
+
+/// Body of `/api/show`. `model_info` holds GGUF metadata with keys prefixed
+/// by the architecture, e.g. `llama.context_length`.
+#[derive(Debug, Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    details: ModelDetails,
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelRequest<'a> {
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// One line of the `/api/pull` progress stream
+#[derive(Debug, Deserialize)]
+struct PullChunk {
+    #[serde(default)]
+    status: String,
+    completed: Option<u64>,
+    total: Option<u64>,
+    error: Option<String>,
+}
+
+/// Ollama backend, talking to its native `/api/generate` and `/api/chat` endpoints
+pub struct OllamaProvider {
+    url: String,
+    model: String,
+    settings: GenerationSettings,
+    usage: Mutex<Option<TokenUsage>>,
+}
+
+impl OllamaProvider {
+    pub fn new(url: &str, model: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            settings: GenerationSettings::default(),
+            usage: Mutex::new(None),
+        }
+    }
+
+    /// Ask Ollama for a specific context window instead of its default, so
+    /// the server and our prompt budget agree on the size
+    pub fn with_context_length(mut self, num_ctx: Option<usize>) -> Self {
+        self.settings.num_ctx = num_ctx;
+        self
+    }
+
+    /// Sampling settings sent as `options`. A `num_ctx` here overrides
+    /// `with_context_length`.
+    pub fn with_generation_settings(mut self, settings: &GenerationSettings) -> Self {
+        let num_ctx = settings.num_ctx.or(self.settings.num_ctx);
+        self.settings = GenerationSettings {
+            num_ctx,
+            ..settings.clone()
+        };
+        self
+    }
+
+    fn options(&self) -> Option<RequestOptions> {
+        request_options(&self.settings)
+    }
+    async fn post_stream<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &T,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let endpoint = format!("{}{}", self.url, path);
+
+        let response = http_client(None)?
+            .post(&endpoint)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| request_error(&self.url, e))?;
+        let response = check_status(response, &self.model).await?;
+
+        let mut output = String::new();
+        llm::read_lines(response, |line| {
+            let chunk = parse_chunk(line)?;
+            let text = chunk.text();
+            if !text.is_empty() {
+                on_token(text);
+                output.push_str(text);
+            }
+            if chunk.done {
+                // Usage is only informational; a panic elsewhere must not stop drafting
+                *self.usage.lock().unwrap_or_else(|e| e.into_inner()) = Some(TokenUsage {
+                    prompt_eval_count: chunk.prompt_eval_count,
+                    eval_count: chunk.eval_count,
+                });
+            }
+            Ok(chunk.done)
+        })
+        .await?;
+
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn context_length(&self) -> usize {
+        self.settings.num_ctx.unwrap_or(llm::DEFAULT_CONTEXT_LENGTH)
+    }
+
+    fn generation_settings(&self) -> GenerationSettings {
+        GenerationSettings {
+            num_ctx: Some(self.context_length()),
+            ..self.settings.clone()
+        }
+    }
+
+    fn last_usage(&self) -> Option<TokenUsage> {
+        *self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn check_health(&self) -> Result<bool, AppError> {
+        check_health(&self.url).await
+    }
+
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let request_body = ChatRequest {
+            model: &self.model,
+            messages,
+            stream: true,
+            options: self.options(),
+        };
+        self.post_stream("/api/chat", &request_body, on_token).await
+    }
+
+    async fn generate(
+        &self,
+        system: &str,
+        prompt: &str,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let request_body = GenerateRequest {
+            model: self.model.clone(),
+            system: system.to_string(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options: self.options(),
+            format: None,
+        };
+        self.post_stream("/api/generate", &request_body, on_token)
+            .await
+    }
+
+    async fn generate_bounded(
+        &self,
+        system: &str,
+        prompt: &str,
+        max_tokens: usize,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let settings = GenerationSettings {
+            num_predict: Some(max_tokens),
+            stop: Vec::new(),
+            ..self.settings.clone()
+        };
+        let request_body = GenerateRequest {
+            model: self.model.clone(),
+            system: system.to_string(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options: request_options(&settings),
+            format: None,
+        };
+        self.post_stream("/api/generate", &request_body, on_token)
+            .await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    async fn generate_structured(
+        &self,
+        system: &str,
+        prompt: &str,
+        schema: &serde_json::Value,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let request_body = GenerateRequest {
+            model: self.model.clone(),
+            system: system.to_string(),
+            prompt: prompt.to_string(),
+            stream: true,
+            options: self.options(),
+            format: Some(schema.clone()),
+        };
+        self.post_stream("/api/generate", &request_body, on_token)
+            .await
+    }
 }
 
 /// Check if Ollama is available at the given URL
@@ -39,71 +351,488 @@ pub async fn check_health(url: &str) -> Result<bool, AppError> {
     }
 }
 
-/// Generate text using Ollama
-pub async fn generate(
-    url: &str,
-    model: &str,
-    system: &str,
-    prompt: &str,
-) -> Result<String, AppError> {
-    let endpoint = format!("{}/api/generate", url.trim_end_matches('/'));
-
-    let request_body = GenerateRequest {
-        model: model.to_string(),
-        system: system.to_string(),
-        prompt: prompt.to_string(),
-        stream: false,
-    };
+/// List the models installed on the Ollama server, sorted by name
+pub async fn list_models(url: &str) -> Result<Vec<OllamaModel>, AppError> {
+    let url = url.trim_end_matches('/');
+    let response = http_client(Some(Duration::from_secs(10)))?
+        .get(format!("{}/api/tags", url))
+        .send()
+        .await
+        .map_err(|e| request_error(url, e))?;
+    let response = check_status(response, "").await?;
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+    let tags: TagsResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse Ollama model list: {}", e)))?;
+
+    let mut models: Vec<OllamaModel> = tags
+        .models
+        .into_iter()
+        .map(|m| OllamaModel {
+            name: m.name,
+            size: m.size,
+            modified_at: m.modified_at,
+            parameter_size: m.details.parameter_size,
+            quantization_level: m.details.quantization_level,
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
 
-    let response = client
-        .post(&endpoint)
-        .json(&request_body)
+/// Look up parameter size, quantization and trained context length of a model
+pub async fn show_model(url: &str, model: &str) -> Result<OllamaModelDetails, AppError> {
+    let url = url.trim_end_matches('/');
+    let response = http_client(Some(Duration::from_secs(30)))?
+        .post(format!("{}/api/show", url))
+        .json(&ModelRequest {
+            model,
+            stream: None,
+        })
         .send()
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                AppError::Internal("Ollama request timed out after 120 seconds".to_string())
-            } else if e.is_connect() {
-                AppError::OllamaUnavailable { url: url.to_string() }
-            } else {
-                AppError::Network(e)
-            }
-        })?;
+        .map_err(|e| request_error(url, e))?;
+    let response = check_status(response, model).await?;
 
-    let status = response.status();
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
+    let show: ShowResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse Ollama model details: {}", e)))?;
+
+    let context_length = show
+        .model_info
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64());
+
+    Ok(OllamaModelDetails {
+        name: model.to_string(),
+        family: show.details.family,
+        parameter_size: show.details.parameter_size,
+        quantization_level: show.details.quantization_level,
+        context_length,
+    })
+}
 
-        // Check if it's a model not found error
-        if error_text.contains("model") && error_text.contains("not found") {
+/// Download a model, reporting each progress line Ollama streams back.
+/// Pulling a model that is already installed only checks its layers.
+pub async fn pull_model(
+    url: &str,
+    model: &str,
+    on_progress: &mut (dyn FnMut(ModelPullProgress) + Send),
+) -> Result<(), AppError> {
+    let url = url.trim_end_matches('/');
+    let response = http_client(None)?
+        .post(format!("{}/api/pull", url))
+        .json(&ModelRequest {
+            model,
+            stream: Some(true),
+        })
+        .send()
+        .await
+        .map_err(|e| request_error(url, e))?;
+    let response = check_status(response, model).await?;
+
+    llm::read_lines(response, |line| {
+        let chunk: PullChunk = serde_json::from_str(line)
+            .map_err(|e| AppError::Internal(format!("Failed to parse Ollama response: {}", e)))?;
+        if let Some(error) = chunk.error {
             return Err(AppError::Internal(format!(
-                "Model '{}' not found. Run 'ollama pull {}' to download it.",
-                model, model
+                "Failed to pull '{}': {}",
+                model, error
             )));
         }
 
-        return Err(AppError::Internal(format!(
-            "Ollama API error ({}): {}",
-            status, error_text
-        )));
+        let done = chunk.status == "success";
+        on_progress(ModelPullProgress {
+            model: model.to_string(),
+            status: chunk.status,
+            completed: chunk.completed,
+            total: chunk.total,
+        });
+        Ok(done)
+    })
+    .await
+}
+
+/// Embed each of `inputs` with an embedding model such as `nomic-embed-text`.
+/// Vectors come back in input order, normalized to unit length by Ollama.
+pub async fn embed(url: &str, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>, AppError> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
     }
 
-    let generate_response: GenerateResponse = response
+    let url = url.trim_end_matches('/');
+    let response = http_client(Some(Duration::from_secs(120)))?
+        .post(format!("{}/api/embed", url))
+        .json(&EmbedRequest {
+            model,
+            input: inputs,
+        })
+        .send()
+        .await
+        .map_err(|e| request_error(url, e))?;
+    let response = check_status(response, model).await?;
+
+    let body: EmbedResponse = response
         .json()
         .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse Ollama embeddings: {}", e)))?;
+
+    if body.embeddings.len() != inputs.len() {
+        return Err(AppError::Internal(format!(
+            "Ollama returned {} embeddings for {} inputs",
+            body.embeddings.len(),
+            inputs.len()
+        )));
+    }
+    Ok(body.embeddings)
+}
+
+fn http_client(timeout: Option<Duration>) -> Result<reqwest::Client, AppError> {
+    let mut builder = reqwest::Client::builder().connect_timeout(Duration::from_secs(10));
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+fn request_error(url: &str, e: reqwest::Error) -> AppError {
+    if e.is_connect() {
+        AppError::OllamaUnavailable {
+            url: url.to_string(),
+        }
+    } else {
+        AppError::Network(e)
+    }
+}
+
+/// Turn an error response into an `AppError`, pointing at `ollama pull`
+/// when the model is missing
+async fn check_status(
+    response: reqwest::Response,
+    model: &str,
+) -> Result<reqwest::Response, AppError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let error_text = response.text().await.unwrap_or_default();
+
+    // Check if it's a model not found error
+    if !model.is_empty() && error_text.contains("model") && error_text.contains("not found") {
+        return Err(AppError::Internal(format!(
+            "Model '{}' not found. Run 'ollama pull {}' to download it.",
+            model, model
+        )));
+    }
+
+    Err(AppError::Internal(format!(
+        "Ollama API error ({}): {}",
+        status, error_text
+    )))
+}
+
+fn parse_chunk(line: &str) -> Result<GenerateChunk, AppError> {
+    let chunk: GenerateChunk = serde_json::from_str(line)
         .map_err(|e| AppError::Internal(format!("Failed to parse Ollama response: {}", e)))?;
 
-    Ok(generate_response.response)
+    if let Some(error) = chunk.error {
+        return Err(AppError::Internal(format!("Ollama error: {}", error)));
+    }
+
+    Ok(chunk)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::llm::test_server;
+
+    #[test]
+    fn test_parse_chunk() {
+        let chunk = parse_chunk(r#"{"model":"llama3.2","response":"Hi","done":false}"#).unwrap();
+        assert_eq!(chunk.text(), "Hi");
+        assert!(!chunk.done);
+
+        let chunk = parse_chunk(r#"{"model":"llama3.2","response":"","done":true}"#).unwrap();
+        assert!(chunk.done);
+    }
+
+    #[test]
+    fn test_parse_chat_chunk() {
+        let chunk = parse_chunk(
+            r#"{"model":"llama3.2","message":{"role":"assistant","content":"Hi"},"done":false}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.text(), "Hi");
+    }
+
+    #[test]
+    fn test_parse_chunk_surfaces_stream_errors() {
+        let err = parse_chunk(r#"{"error":"out of memory"}"#).unwrap_err();
+        assert!(err.to_string().contains("out of memory"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_stub_server() {
+        let body = [
+            r#"{"model":"llama3.2","response":"Fix ","done":false}"#,
+            r#"{"model":"llama3.2","response":"login","done":false}"#,
+            r#"{"model":"llama3.2","response":"","done":true,"prompt_eval_count":26,"eval_count":2}"#,
+        ]
+        .join("\n");
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let provider = OllamaProvider::new(&url, "llama3.2");
+        assert_eq!(provider.last_usage(), None);
+        let mut tokens = Vec::new();
+        let output = provider
+            .generate("system", "prompt", &mut |t| tokens.push(t.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Fix login");
+        assert_eq!(tokens, vec!["Fix ", "login"]);
+        assert_eq!(
+            provider.last_usage(),
+            Some(TokenUsage {
+                prompt_eval_count: Some(26),
+                eval_count: Some(2),
+            })
+        );
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/generate"));
+        assert!(requests[0].contains(r#""stream":true"#));
+        assert!(!requests[0].contains("num_ctx"));
+    }
+
+    #[tokio::test]
+    async fn test_sends_configured_context_length() {
+        let body = r#"{"response":"ok","done":true}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let provider = OllamaProvider::new(&url, "llama3.2").with_context_length(Some(16384));
+        provider.generate("s", "p", &mut |_| {}).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(r#""options":{"num_ctx":16384}"#));
+    }
+
+    #[tokio::test]
+    async fn test_sends_generation_settings() {
+        let body = r#"{"response":"ok","done":true}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let settings = GenerationSettings {
+            temperature: Some(0.2),
+            seed: Some(42),
+            stop: vec!["---".to_string()],
+            ..Default::default()
+        };
+        let provider = OllamaProvider::new(&url, "llama3.2")
+            .with_context_length(Some(8192))
+            .with_generation_settings(&settings);
+        assert_eq!(provider.context_length(), 8192);
+        provider.generate("s", "p", &mut |_| {}).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0]
+            .contains(r#""options":{"num_ctx":8192,"temperature":0.2,"seed":42,"stop":["---"]}"#));
+    }
+
+    #[tokio::test]
+    async fn test_bounded_generation_drops_template_limits() {
+        let body = r#"{"response":"ok","done":true}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let settings = GenerationSettings {
+            temperature: Some(0.2),
+            num_predict: Some(64),
+            stop: vec!["\n\n".to_string()],
+            ..Default::default()
+        };
+        let provider = OllamaProvider::new(&url, "llama3.2")
+            .with_context_length(Some(8192))
+            .with_generation_settings(&settings);
+        provider
+            .generate_bounded("s", "p", 300, &mut |_| {})
+            .await
+            .unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0]
+            .contains(r#""options":{"num_ctx":8192,"temperature":0.2,"num_predict":300}"#));
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_sends_schema() {
+        let body = r#"{"response":"{\"title\":\"x\"}","done":true}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let provider = OllamaProvider::new(&url, "llama3.2");
+        let schema = serde_json::json!({"type": "object"});
+        let output = provider
+            .generate_structured("s", "p", &schema, &mut |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(output, r#"{"title":"x"}"#);
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(r#""format":{"type":"object"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_stub_server() {
+        let body = [
+            r#"{"message":{"role":"assistant","content":"Shorter."},"done":false}"#,
+            r#"{"message":{"role":"assistant","content":""},"done":true}"#,
+        ]
+        .join("\n");
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let provider = OllamaProvider::new(&url, "llama3.2");
+        let output = provider
+            .chat(&[ChatMessage::user("Shorten it")], &mut |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Shorter.");
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/chat"));
+    }
+
+    #[tokio::test]
+    async fn test_model_not_found() {
+        let body = r#"{"error":"model \"nope\" not found, try pulling it first"}"#.to_string();
+        let (url, _server) = test_server::serve(404, "application/json", body).await;
+
+        let provider = OllamaProvider::new(&url, "nope");
+        let err = provider.generate("s", "p", &mut |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("ollama pull nope"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_closed_before_done() {
+        let body = r#"{"response":"partial","done":false}"#.to_string();
+        let (url, _server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let provider = OllamaProvider::new(&url, "llama3.2");
+        let err = provider.generate("s", "p", &mut |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("closed the stream"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let body = r#"{"models":[
+            {"name":"qwen2.5:7b","modified_at":"2024-10-01T10:00:00Z","size":4683087332,
+             "details":{"family":"qwen2","parameter_size":"7.6B","quantization_level":"Q4_K_M"}},
+            {"name":"llama3.2:latest","modified_at":"2024-09-25T08:00:00Z","size":2019393189,
+             "details":{"family":"llama","parameter_size":"3.2B","quantization_level":"Q4_K_M"}}
+        ]}"#
+        .to_string();
+        let (url, server) = test_server::serve(200, "application/json", body).await;
+
+        let models = list_models(&url).await.unwrap();
+
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["llama3.2:latest", "qwen2.5:7b"]);
+        assert_eq!(models[1].size, 4683087332);
+        assert_eq!(models[1].parameter_size.as_deref(), Some("7.6B"));
+        assert_eq!(models[1].quantization_level.as_deref(), Some("Q4_K_M"));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/tags"));
+    }
+
+    #[tokio::test]
+    async fn test_show_model_reads_context_length() {
+        let body =
+            r#"{"details":{"family":"llama","parameter_size":"3.2B","quantization_level":"Q4_K_M"},
+            "model_info":{"general.architecture":"llama","llama.context_length":131072}}"#
+                .to_string();
+        let (url, server) = test_server::serve(200, "application/json", body).await;
+
+        let details = show_model(&url, "llama3.2").await.unwrap();
+        assert_eq!(details.name, "llama3.2");
+        assert_eq!(details.family.as_deref(), Some("llama"));
+        assert_eq!(details.context_length, Some(131072));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/show"));
+        assert!(requests[0].contains(r#"{"model":"llama3.2"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_show_unknown_model() {
+        let body = r#"{"error":"model 'nope' not found"}"#.to_string();
+        let (url, _server) = test_server::serve(404, "application/json", body).await;
+
+        let err = show_model(&url, "nope").await.unwrap_err();
+        assert!(err.to_string().contains("ollama pull nope"));
+    }
+
+    #[tokio::test]
+    async fn test_embed() {
+        let body = r#"{"model":"nomic-embed-text","embeddings":[[0.6,0.8],[1.0,0.0]]}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/json", body).await;
+
+        let inputs = vec!["vpn drops".to_string(), "printer offline".to_string()];
+        let vectors = embed(&url, "nomic-embed-text", &inputs).await.unwrap();
+        assert_eq!(vectors, vec![vec![0.6, 0.8], vec![1.0, 0.0]]);
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/embed"));
+        assert!(requests[0].contains(r#""input":["vpn drops","printer offline"]"#));
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_reports_progress() {
+        let body = [
+            r#"{"status":"pulling manifest"}"#,
+            r#"{"status":"pulling 6a0746a1ec1a","digest":"sha256:6a07","total":2019377376,"completed":1048576}"#,
+            r#"{"status":"verifying sha256 digest"}"#,
+            r#"{"status":"success"}"#,
+        ]
+        .join("\n");
+        let (url, server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let mut progress = Vec::new();
+        pull_model(&url, "llama3.2", &mut |p| progress.push(p))
+            .await
+            .unwrap();
+
+        let statuses: Vec<&str> = progress.iter().map(|p| p.status.as_str()).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                "pulling manifest",
+                "pulling 6a0746a1ec1a",
+                "verifying sha256 digest",
+                "success"
+            ]
+        );
+        assert_eq!(progress[1].completed, Some(1048576));
+        assert_eq!(progress[1].total, Some(2019377376));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/pull"));
+        assert!(requests[0].contains(r#""stream":true"#));
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_surfaces_errors() {
+        let body = r#"{"error":"pull model manifest: file does not exist"}"#.to_string();
+        let (url, _server) = test_server::serve(200, "application/x-ndjson", body).await;
+
+        let err = pull_model(&url, "nope", &mut |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("file does not exist"));
+    }
 
     #[tokio::test]
     #[ignore] // Requires Ollama running
@@ -116,13 +845,14 @@ mod tests {
     #[tokio::test]
     #[ignore] // Requires Ollama running with a model
     async fn test_generate() {
-        let result = generate(
-            "http://localhost:11434",
-            "llama3.2",
-            "You are a helpful assistant.",
-            "Say 'test' and nothing else.",
-        )
-        .await;
+        let provider = OllamaProvider::new("http://localhost:11434", "llama3.2");
+        let result = provider
+            .generate(
+                "You are a helpful assistant.",
+                "Say 'test' and nothing else.",
+                &mut |_| {},
+            )
+            .await;
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -0,0 +1,396 @@
+use crate::error::AppError;
+use crate::models::GenerationSettings;
+use crate::services::llm::{self, ChatMessage, LlmProvider, TokenCallback};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    stop: &'a [String],
+}
+
+/// One `data:` event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Delta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+/// Backend for servers speaking the OpenAI `/v1/chat/completions` API,
+/// such as llama.cpp's server and vLLM
+pub struct OpenAiCompatProvider {
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    settings: GenerationSettings,
+}
+
+impl OpenAiCompatProvider {
+    pub fn new(url: &str, model: &str, api_key: Option<String>) -> Self {
+        // Accept both "http://host:8080" and "http://host:8080/v1"
+        let url = url.trim_end_matches('/');
+        let url = url.strip_suffix("/v1").unwrap_or(url);
+
+        Self {
+            url: url.to_string(),
+            model: model.to_string(),
+            api_key: api_key.filter(|k| !k.is_empty()),
+            settings: GenerationSettings::default(),
+        }
+    }
+
+    /// The context window is fixed when the server starts (e.g. llama.cpp's
+    /// `-c`), so this only tells us how much room prompts have
+    pub fn with_context_length(mut self, context_length: Option<usize>) -> Self {
+        self.settings.num_ctx = context_length;
+        self
+    }
+
+    /// Sampling settings sent with every request. `num_predict` maps to
+    /// `max_tokens`; `num_ctx` only adjusts the prompt budget, like
+    /// `with_context_length`.
+    pub fn with_generation_settings(mut self, settings: &GenerationSettings) -> Self {
+        let num_ctx = settings.num_ctx.or(self.settings.num_ctx);
+        self.settings = GenerationSettings {
+            num_ctx,
+            ..settings.clone()
+        };
+        self
+    }
+
+    /// Stream a chat completion sampled with `settings`
+    async fn stream_chat(
+        &self,
+        messages: &[ChatMessage],
+        settings: &GenerationSettings,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let request_body = ChatCompletionRequest {
+            model: &self.model,
+            messages,
+            stream: true,
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            seed: settings.seed,
+            max_tokens: settings.num_predict,
+            stop: &settings.stop,
+        };
+
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        let response = self
+            .request(&client, reqwest::Method::POST, "/chat/completions")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    AppError::LlmUnavailable {
+                        url: self.url.clone(),
+                    }
+                } else {
+                    AppError::Network(e)
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AppError::Internal(format!(
+                "LLM API error ({}): {}",
+                status, error_text
+            )));
+        }
+
+        let mut output = String::new();
+        llm::read_lines(response, |line| {
+            let Some(chunk) = parse_event(line)? else {
+                return Ok(false);
+            };
+            match chunk {
+                SseEvent::Done => Ok(true),
+                SseEvent::Chunk(chunk) => {
+                    let mut finished = false;
+                    for choice in chunk.choices {
+                        if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+                            on_token(&text);
+                            output.push_str(&text);
+                        }
+                        finished |= choice.finish_reason.is_some();
+                    }
+                    Ok(finished)
+                }
+            }
+        })
+        .await?;
+
+        Ok(output)
+    }
+
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        path: &str,
+    ) -> reqwest::RequestBuilder {
+        let builder = client.request(method, format!("{}/v1{}", self.url, path));
+        match &self.api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatProvider {
+    fn name(&self) -> &'static str {
+        "OpenAI-compatible"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn context_length(&self) -> usize {
+        self.settings.num_ctx.unwrap_or(llm::DEFAULT_CONTEXT_LENGTH)
+    }
+
+    fn generation_settings(&self) -> GenerationSettings {
+        GenerationSettings {
+            num_ctx: Some(self.context_length()),
+            ..self.settings.clone()
+        }
+    }
+
+    async fn check_health(&self) -> Result<bool, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        match self
+            .request(&client, reqwest::Method::GET, "/models")
+            .send()
+            .await
+        {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        self.stream_chat(messages, &self.settings, on_token).await
+    }
+
+    async fn generate_bounded(
+        &self,
+        system: &str,
+        prompt: &str,
+        max_tokens: usize,
+        on_token: TokenCallback<'_>,
+    ) -> Result<String, AppError> {
+        let settings = GenerationSettings {
+            num_predict: Some(max_tokens),
+            stop: Vec::new(),
+            ..self.settings.clone()
+        };
+        let messages = [ChatMessage::system(system), ChatMessage::user(prompt)];
+        self.stream_chat(&messages, &settings, on_token).await
+    }
+}
+
+#[derive(Debug)]
+enum SseEvent {
+    Chunk(ChatCompletionChunk),
+    Done,
+}
+
+/// Parse one server-sent-events line. Comments, keep-alives and non-data
+/// fields yield `None`.
+fn parse_event(line: &str) -> Result<Option<SseEvent>, AppError> {
+    let Some(data) = line.strip_prefix("data:") else {
+        return Ok(None);
+    };
+    let data = data.trim();
+
+    if data == "[DONE]" {
+        return Ok(Some(SseEvent::Done));
+    }
+
+    let value: serde_json::Value = serde_json::from_str(data)
+        .map_err(|e| AppError::Internal(format!("Failed to parse LLM response: {}", e)))?;
+
+    if let Some(error) = value.get("error") {
+        let message = error["message"].as_str().unwrap_or("unknown error");
+        return Err(AppError::Internal(format!("LLM error: {}", message)));
+    }
+
+    let chunk = serde_json::from_value(value)
+        .map_err(|e| AppError::Internal(format!("Failed to parse LLM response: {}", e)))?;
+    Ok(Some(SseEvent::Chunk(chunk)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm::test_server;
+
+    #[test]
+    fn test_strips_v1_suffix() {
+        let provider = OpenAiCompatProvider::new("http://localhost:8080/v1/", "m", None);
+        assert_eq!(provider.url, "http://localhost:8080");
+    }
+
+    #[test]
+    fn test_parse_event_ignores_non_data_lines() {
+        assert!(parse_event(": keep-alive").unwrap().is_none());
+        assert!(parse_event("event: message").unwrap().is_none());
+        assert!(matches!(
+            parse_event("data: [DONE]").unwrap(),
+            Some(SseEvent::Done)
+        ));
+    }
+
+    #[test]
+    fn test_parse_event_surfaces_errors() {
+        let err =
+            parse_event(r#"data: {"error":{"message":"context length exceeded"}}"#).unwrap_err();
+        assert!(err.to_string().contains("context length exceeded"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_against_stub_server() {
+        let body = [
+            r#"data: {"choices":[{"index":0,"delta":{"role":"assistant"},"finish_reason":null}]}"#,
+            "",
+            r#"data: {"choices":[{"index":0,"delta":{"content":"Fix "},"finish_reason":null}]}"#,
+            "",
+            r#"data: {"choices":[{"index":0,"delta":{"content":"login"},"finish_reason":null}]}"#,
+            "",
+            r#"data: {"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+            "",
+            "data: [DONE]",
+            "",
+        ]
+        .join("\n");
+        let (url, server) = test_server::serve(200, "text/event-stream", body).await;
+
+        let provider = OpenAiCompatProvider::new(&url, "qwen2.5", Some("secret".to_string()));
+        let mut tokens = Vec::new();
+        let output = provider
+            .generate("system", "prompt", &mut |t| tokens.push(t.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(output, "Fix login");
+        assert_eq!(tokens, vec!["Fix ", "login"]);
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /v1/chat/completions"));
+        assert!(requests[0]
+            .to_lowercase()
+            .contains("authorization: bearer secret"));
+        assert!(requests[0].contains(r#""role":"system""#));
+    }
+
+    #[tokio::test]
+    async fn test_sends_generation_settings() {
+        let body = "data: [DONE]\n".to_string();
+        let (url, server) = test_server::serve(200, "text/event-stream", body).await;
+
+        let settings = GenerationSettings {
+            temperature: Some(0.2),
+            num_ctx: Some(16384),
+            num_predict: Some(512),
+            ..Default::default()
+        };
+        let provider =
+            OpenAiCompatProvider::new(&url, "qwen2.5", None).with_generation_settings(&settings);
+        assert_eq!(provider.context_length(), 16384);
+        provider.generate("s", "p", &mut |_| {}).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(r#""temperature":0.2"#));
+        assert!(requests[0].contains(r#""max_tokens":512"#));
+        assert!(!requests[0].contains("stop"));
+        assert!(!requests[0].contains("num_ctx"));
+    }
+
+    #[tokio::test]
+    async fn test_bounded_generation_drops_template_limits() {
+        let body = "data: [DONE]\n".to_string();
+        let (url, server) = test_server::serve(200, "text/event-stream", body).await;
+
+        let settings = GenerationSettings {
+            temperature: Some(0.2),
+            num_predict: Some(64),
+            stop: vec!["\n\n".to_string()],
+            ..Default::default()
+        };
+        let provider =
+            OpenAiCompatProvider::new(&url, "qwen2.5", None).with_generation_settings(&settings);
+        provider
+            .generate_bounded("s", "p", 300, &mut |_| {})
+            .await
+            .unwrap();
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(r#""temperature":0.2"#));
+        assert!(requests[0].contains(r#""max_tokens":300"#));
+        assert!(!requests[0].contains("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_check_health_against_stub_server() {
+        let body = r#"{"object":"list","data":[{"id":"qwen2.5"}]}"#.to_string();
+        let (url, server) = test_server::serve(200, "application/json", body).await;
+
+        let provider = OpenAiCompatProvider::new(&url, "qwen2.5", None);
+        assert!(provider.check_health().await.unwrap());
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /v1/models"));
+    }
+
+    #[tokio::test]
+    async fn test_api_error_status() {
+        let body = r#"{"error":{"message":"model not loaded"}}"#.to_string();
+        let (url, _server) = test_server::serve(500, "application/json", body).await;
+
+        let provider = OpenAiCompatProvider::new(&url, "qwen2.5", None);
+        let err = provider.generate("s", "p", &mut |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("model not loaded"));
+    }
+}
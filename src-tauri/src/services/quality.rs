@@ -1,6 +1,9 @@
-use crate::models::{NewArticle, QualityScore};
+use crate::models::{NewArticle, QualityScore, Template};
+use crate::services::conformance;
 
-pub fn score(article: &NewArticle) -> QualityScore {
+/// Score an article for completeness. With the article's `template`, the
+/// sections are also checked against it and any mismatch is warned about.
+pub fn score(article: &NewArticle, template: Option<&Template>) -> QualityScore {
     let mut overall: u8 = 0;
     let mut warnings = Vec::new();
 
@@ -69,6 +72,12 @@ pub fn score(article: &NewArticle) -> QualityScore {
         warnings.push("No code blocks detected".to_string());
     }
 
+    // Template conformance
+    if let Some(template) = template {
+        let report = conformance::check(&article.content_markdown, template);
+        warnings.extend(conformance::warnings(&report));
+    }
+
     // Word count
     let word_count = article.content_markdown.split_whitespace().count();
 
@@ -101,6 +110,7 @@ mod tests {
     fn test_score_complete_article() {
         let article = NewArticle {
             ticket_key: Some("TEST-123".to_string()),
+            source_ticket_keys: vec![],
             title: "Fix Login Issue".to_string(),
             problem: "Users cannot log in due to timeout error".to_string(),
             solution: "1. Clear browser cache\n2. Restart the application\n3. Try logging in again\n\nThe solution involves multiple steps to resolve the timeout.".to_string(),
@@ -110,9 +120,12 @@ mod tests {
             tags: vec!["login".to_string(), "timeout".to_string()],
             content_markdown: "# Fix Login Issue\n\n## Problem\nUsers cannot log in\n\n## Solution\n1. Clear cache\n2. Restart\n3. Login\n\n```bash\nrm -rf ~/.cache\n```".to_string(),
             template_id: Some("tpl-troubleshoot".to_string()),
+            provenance_id: None,
+            language: None,
+            translation_of: None,
         };
 
-        let score = score(&article);
+        let score = score(&article, None);
         assert_eq!(score.overall, 100); // Should get max score
         assert!(score.has_title);
         assert!(score.has_problem);
@@ -126,6 +139,7 @@ mod tests {
     fn test_score_minimal_article() {
         let article = NewArticle {
             ticket_key: None,
+            source_ticket_keys: vec![],
             title: "Short".to_string(),
             problem: "Prob".to_string(),
             solution: "Sol".to_string(),
@@ -135,12 +149,56 @@ mod tests {
             tags: vec![],
             content_markdown: "Short".to_string(),
             template_id: None,
+            provenance_id: None,
+            language: None,
+            translation_of: None,
         };
 
-        let score = score(&article);
+        let score = score(&article, None);
         assert!(score.overall < 50); // Should get low score
         assert!(!score.has_title); // Title too short
         assert!(!score.has_problem); // Problem too short
         assert!(!score.has_solution); // Solution too short
     }
+
+    #[test]
+    fn test_score_warns_about_template_sections() {
+        let template = Template {
+            id: "tpl-troubleshoot".to_string(),
+            name: "Troubleshooting".to_string(),
+            slug: "troubleshooting".to_string(),
+            description: String::new(),
+            system_prompt: String::new(),
+            output_structure: "# Title\n## Problem\n## Cause\n## Resolution".to_string(),
+            field_mapping: Default::default(),
+            generation: Default::default(),
+            model: None,
+            is_builtin: true,
+            created_at: "2024-01-01".to_string(),
+        };
+        let article = NewArticle {
+            ticket_key: None,
+            source_ticket_keys: vec![],
+            title: "VPN drops".to_string(),
+            problem: "Tunnel resets".to_string(),
+            solution: "Restart".to_string(),
+            expected_result: None,
+            prerequisites: None,
+            additional_notes: None,
+            tags: vec![],
+            content_markdown: "# VPN drops\n## Problem\nTunnel resets\n## Resolution\nRestart"
+                .to_string(),
+            template_id: Some("tpl-troubleshoot".to_string()),
+            provenance_id: None,
+            language: None,
+            translation_of: None,
+        };
+
+        let warnings = score(&article, Some(&template)).warnings;
+        assert!(warnings.contains(&"Missing template section: Cause".to_string()));
+        assert!(!score(&article, None)
+            .warnings
+            .iter()
+            .any(|w| w.contains("template")));
+    }
 }
@@ -0,0 +1,278 @@
+use crate::models::{Article, ArticleReference, JiraTicket};
+use crate::services::tag_suggester::STOPWORDS;
+use std::collections::{HashMap, HashSet};
+
+/// Articles put in the drafting prompt when the caller does not ask for a number
+pub const DEFAULT_REFERENCE_COUNT: usize = 3;
+
+/// Length of the summary shown to the model for each article
+const SUMMARY_CHARS: usize = 280;
+
+/// BM25 parameters, the usual defaults
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// Matches on the title say more than matches in the body
+const TITLE_WEIGHT: usize = 3;
+
+/// Scores below this share of the best match are noise from common words
+const MIN_RELATIVE_SCORE: f32 = 0.3;
+
+/// The `limit` articles most relevant to `tickets`, best first, ranked with
+/// BM25 over their title, tags, problem and solution. Articles drafted from
+/// one of the same tickets are left out; they are earlier drafts, not
+/// related material.
+pub fn retrieve(
+    tickets: &[JiraTicket],
+    articles: &[Article],
+    limit: usize,
+) -> Vec<ArticleReference> {
+    let keys: HashSet<&str> = tickets.iter().map(|t| t.key.as_str()).collect();
+    let candidates: Vec<&Article> = articles
+        .iter()
+        .filter(|a| {
+            !a.source_ticket_keys
+                .iter()
+                .any(|k| keys.contains(k.as_str()))
+        })
+        .filter(|a| !a.ticket_key.as_deref().is_some_and(|k| keys.contains(k)))
+        .collect();
+    if candidates.is_empty() || limit == 0 {
+        return Vec::new();
+    }
+
+    let query: HashSet<String> = tickets
+        .iter()
+        .flat_map(|t| {
+            let mut text = format!("{} {}", t.summary, t.description.as_deref().unwrap_or(""));
+            for extra in t.labels.iter().chain(&t.components) {
+                text.push(' ');
+                text.push_str(extra);
+            }
+            terms(&text)
+        })
+        .collect();
+
+    let documents: Vec<HashMap<String, usize>> =
+        candidates.iter().map(|a| document_terms(a)).collect();
+    let lengths: Vec<usize> = documents.iter().map(|d| d.values().sum()).collect();
+    let average_length = lengths.iter().sum::<usize>() as f32 / documents.len() as f32;
+
+    // Inverse document frequency of each query term found in any article
+    let total = documents.len() as f32;
+    let idf: HashMap<&str, f32> = query
+        .iter()
+        .filter_map(|term| {
+            let containing = documents.iter().filter(|d| d.contains_key(term)).count() as f32;
+            (containing > 0.0).then(|| {
+                let idf = ((total - containing + 0.5) / (containing + 0.5) + 1.0).ln();
+                (term.as_str(), idf)
+            })
+        })
+        .collect();
+
+    let mut scored: Vec<(usize, f32)> = documents
+        .iter()
+        .zip(&lengths)
+        .enumerate()
+        .map(|(i, (document, length))| {
+            let norm = K1 * (1.0 - B + B * *length as f32 / average_length.max(1.0));
+            let score = idf
+                .iter()
+                .filter_map(|(term, idf)| {
+                    let frequency = *document.get(*term)? as f32;
+                    Some(idf * frequency * (K1 + 1.0) / (frequency + norm))
+                })
+                .sum::<f32>();
+            (i, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then(candidates[a.0].id.cmp(&candidates[b.0].id))
+    });
+    let best = scored.first().map(|(_, score)| *score).unwrap_or(0.0);
+
+    scored
+        .into_iter()
+        .filter(|(_, score)| *score >= best * MIN_RELATIVE_SCORE)
+        .take(limit)
+        .map(|(i, score)| reference(candidates[i], score))
+        .collect()
+}
+
+fn reference(article: &Article, score: f32) -> ArticleReference {
+    ArticleReference {
+        article_id: article.id,
+        title: article.title.clone(),
+        summary: summary(article),
+        ticket_key: article.ticket_key.clone(),
+        confluence_url: article.confluence_url.clone(),
+        score,
+    }
+}
+
+/// The problem statement, cut at a sentence end near `SUMMARY_CHARS`
+fn summary(article: &Article) -> String {
+    let text = article
+        .problem
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() <= SUMMARY_CHARS {
+        return text;
+    }
+
+    let cut: String = text.chars().take(SUMMARY_CHARS).collect();
+    match cut.rfind(". ") {
+        Some(end) if end > SUMMARY_CHARS / 2 => cut[..=end].to_string(),
+        _ => format!("{}...", cut.trim_end()),
+    }
+}
+
+fn document_terms(article: &Article) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for term in terms(&article.title) {
+        *counts.entry(term).or_insert(0) += TITLE_WEIGHT;
+    }
+    let body = format!(
+        "{} {} {}",
+        article.tags.join(" "),
+        article.problem,
+        article.solution
+    );
+    for term in terms(&body) {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.len() >= 3 && !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ArticleStatus;
+
+    fn article(id: i64, ticket_key: &str, title: &str, problem: &str) -> Article {
+        Article {
+            id,
+            ticket_key: Some(ticket_key.to_string()),
+            source_ticket_keys: vec![ticket_key.to_string()],
+            title: title.to_string(),
+            problem: problem.to_string(),
+            solution: String::new(),
+            expected_result: None,
+            prerequisites: None,
+            additional_notes: None,
+            tags: vec![],
+            content_markdown: String::new(),
+            status: ArticleStatus::Published,
+            confluence_page_id: None,
+            confluence_url: None,
+            confluence_space_key: None,
+            quality_score: None,
+            template_id: None,
+            language: "en".to_string(),
+            translation_of: None,
+            created_at: "2024-01-01".to_string(),
+            updated_at: "2024-01-01".to_string(),
+        }
+    }
+
+    fn ticket(key: &str, summary: &str) -> JiraTicket {
+        JiraTicket {
+            key: key.to_string(),
+            summary: summary.to_string(),
+            description: None,
+            status: "Resolved".to_string(),
+            priority: None,
+            resolution: None,
+            labels: vec!["vpn".to_string()],
+            components: vec![],
+            comments: vec![],
+            linked_issues: vec![],
+            timeline: vec![],
+            created: "2024-01-01T09:00:00".to_string(),
+            updated: "2024-01-01T12:00:00".to_string(),
+        }
+    }
+
+    fn knowledge_base() -> Vec<Article> {
+        vec![
+            article(
+                1,
+                "SUP-1",
+                "Printer offline after driver update",
+                "The office printer shows offline.",
+            ),
+            article(
+                2,
+                "SUP-2",
+                "VPN tunnel drops every few minutes",
+                "The VPN tunnel resets when idle.",
+            ),
+            article(
+                3,
+                "SUP-3",
+                "Outlook cannot connect over VPN",
+                "Outlook stays disconnected while on VPN.",
+            ),
+            article(
+                4,
+                "SUP-4",
+                "Password reset link expired",
+                "The reset link in the email has expired.",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_retrieves_relevant_articles_first() {
+        let refs = retrieve(
+            &[ticket("SUP-9", "VPN tunnel keeps dropping")],
+            &knowledge_base(),
+            3,
+        );
+
+        let ids: Vec<i64> = refs.iter().map(|r| r.article_id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert_eq!(refs[0].title, "VPN tunnel drops every few minutes");
+        assert_eq!(refs[0].summary, "The VPN tunnel resets when idle.");
+    }
+
+    #[test]
+    fn test_skips_articles_of_the_same_ticket() {
+        let refs = retrieve(
+            &[ticket("SUP-2", "VPN tunnel keeps dropping")],
+            &knowledge_base(),
+            3,
+        );
+        assert!(refs.iter().all(|r| r.article_id != 2));
+    }
+
+    #[test]
+    fn test_respects_limit_and_empty_matches() {
+        let tickets = [ticket("SUP-9", "VPN tunnel keeps dropping")];
+        assert_eq!(retrieve(&tickets, &knowledge_base(), 1).len(), 1);
+        assert!(retrieve(&tickets, &knowledge_base(), 0).is_empty());
+
+        let mut unrelated = ticket("SUP-9", "Monitor flickers");
+        unrelated.labels.clear();
+        assert!(retrieve(&[unrelated], &knowledge_base(), 3).is_empty());
+    }
+
+    #[test]
+    fn test_summary_is_cut_at_a_sentence() {
+        let long = format!("{}. {}", "word ".repeat(40).trim(), "more ".repeat(40));
+        let summary = summary(&article(1, "SUP-1", "Title", &long));
+        assert!(summary.ends_with("word."));
+    }
+}
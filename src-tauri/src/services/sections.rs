@@ -0,0 +1,203 @@
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+/// A heading and the content under it, as byte ranges into the source markdown
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub heading: String,
+    pub level: u8,
+    /// Start of the heading line
+    pub start: usize,
+    /// First byte after the heading line
+    pub body_start: usize,
+    /// End of the section: the next heading of the same or a higher level
+    pub end: usize,
+}
+
+impl Section {
+    pub fn body<'a>(&self, markdown: &'a str) -> &'a str {
+        &markdown[self.body_start..self.end]
+    }
+}
+
+/// Find every heading in the document. Each section runs until the next
+/// heading of the same or a higher level, so it includes its subsections.
+/// Lines that merely look like headings inside code blocks are ignored.
+pub fn parse_sections(markdown: &str) -> Vec<Section> {
+    let mut headings: Vec<(String, u8, usize, usize)> = Vec::new();
+    let mut current: Option<(String, u8, usize, usize)> = None;
+
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((String::new(), heading_level(level), range.start, range.end));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((heading, ..)) = current.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(heading) = current.take() {
+                    headings.push(heading);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    headings
+        .iter()
+        .enumerate()
+        .map(|(i, (heading, level, start, body_start))| {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|(_, next_level, ..)| next_level <= level)
+                .map(|(_, _, next_start, _)| *next_start)
+                .unwrap_or(markdown.len());
+
+            Section {
+                heading: heading.trim().to_string(),
+                level: *level,
+                start: *start,
+                body_start: *body_start,
+                end,
+            }
+        })
+        .collect()
+}
+
+/// Find a section by heading text, ignoring case and surrounding whitespace.
+/// A leading `#` marker in `heading` is accepted, so "## Resolution" works too.
+pub fn find_section(markdown: &str, heading: &str) -> Option<Section> {
+    let wanted = heading.trim().trim_start_matches('#').trim();
+    parse_sections(markdown)
+        .into_iter()
+        .find(|s| s.heading.eq_ignore_ascii_case(wanted))
+}
+
+/// Replace the body of `section`, keeping its heading line and every byte
+/// outside the section untouched. The whitespace that separated the old body
+/// from its surroundings is kept, so the document layout does not shift.
+pub fn replace_section_body(markdown: &str, section: &Section, new_body: &str) -> String {
+    let old_body = section.body(markdown);
+    let trimmed = old_body.trim();
+
+    let (leading, trailing) = if trimmed.is_empty() {
+        // Empty sections have no layout to keep; separate the body with blank lines
+        let trailing = if section.end < markdown.len() {
+            "\n\n"
+        } else {
+            "\n"
+        };
+        ("\n", trailing)
+    } else {
+        let lead_len = old_body.len() - old_body.trim_start().len();
+        let trail_len = old_body.len() - old_body.trim_end().len();
+        (
+            &old_body[..lead_len],
+            &old_body[old_body.len() - trail_len..],
+        )
+    };
+
+    let mut result = String::with_capacity(markdown.len() + new_body.len());
+    result.push_str(&markdown[..section.body_start]);
+    result.push_str(leading);
+    result.push_str(new_body.trim());
+    result.push_str(trailing);
+    result.push_str(&markdown[section.end..]);
+    result
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "# VPN drops\n\n## Problem\nThe VPN disconnects.\n\n## Resolution\n1. Restart\n\n### Notes\nSub note\n\n## Expected Result\nIt works.\n";
+
+    #[test]
+    fn test_parse_sections() {
+        let sections = parse_sections(ARTICLE);
+        let headings: Vec<_> = sections
+            .iter()
+            .map(|s| (s.heading.as_str(), s.level))
+            .collect();
+        assert_eq!(
+            headings,
+            vec![
+                ("VPN drops", 1),
+                ("Problem", 2),
+                ("Resolution", 2),
+                ("Notes", 3),
+                ("Expected Result", 2)
+            ]
+        );
+
+        let resolution = &sections[2];
+        assert_eq!(
+            resolution.body(ARTICLE),
+            "1. Restart\n\n### Notes\nSub note\n\n"
+        );
+        // The title section spans the whole document
+        assert_eq!(sections[0].end, ARTICLE.len());
+    }
+
+    #[test]
+    fn test_ignores_headings_in_code_blocks() {
+        let md = "## Steps\n```bash\n# comment, not a heading\n```\n## Next\n";
+        let sections = parse_sections(md);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].heading, "Next");
+    }
+
+    #[test]
+    fn test_find_section() {
+        assert!(find_section(ARTICLE, "resolution").is_some());
+        assert!(find_section(ARTICLE, "## Expected Result").is_some());
+        assert!(find_section(ARTICLE, "Cause").is_none());
+    }
+
+    #[test]
+    fn test_replace_section_keeps_rest_identical() {
+        let section = find_section(ARTICLE, "Problem").unwrap();
+        let result = replace_section_body(ARTICLE, &section, "\nThe VPN drops every 5 minutes.\n");
+
+        assert_eq!(
+            result,
+            ARTICLE.replace("The VPN disconnects.", "The VPN drops every 5 minutes.")
+        );
+        assert_eq!(
+            &result[..section.body_start],
+            &ARTICLE[..section.body_start]
+        );
+    }
+
+    #[test]
+    fn test_replace_empty_section() {
+        let md = "## Cause\n\n## Resolution\nSteps\n";
+        let section = find_section(md, "Cause").unwrap();
+        let result = replace_section_body(md, &section, "Expired certificate");
+        assert_eq!(
+            result,
+            "## Cause\n\nExpired certificate\n\n## Resolution\nSteps\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_last_section() {
+        let section = find_section(ARTICLE, "Expected Result").unwrap();
+        let result = replace_section_body(ARTICLE, &section, "The tunnel stays up.");
+        assert!(result.ends_with("## Expected Result\nThe tunnel stays up.\n"));
+        assert!(result.starts_with(&ARTICLE[..section.start]));
+    }
+}
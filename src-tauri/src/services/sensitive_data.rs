@@ -1,45 +1,70 @@
-use crate::models::FlaggedSection;
-use regex::Regex;
+use crate::models::{FlaggedSection, JiraTicket, Redaction};
+use regex::{Captures, Regex};
+
+/// A kind of sensitive data: its pattern and how it is reported and redacted
+struct Detector {
+    pattern: &'static str,
+    pattern_type: &'static str,
+    severity: &'static str,
+    description: &'static str,
+    /// Placeholder prefix when redacting, e.g. `IP` for `<IP_1>`
+    label: &'static str,
+}
+
+/// Pattern definitions with severity. When redacting, a `value` group is
+/// replaced instead of the whole match, so `password: x` stays readable.
+/// Earlier detectors are redacted first: a connection string goes as a
+/// whole before the IP inside it is found.
+const DETECTORS: &[Detector] = &[
+    Detector {
+        pattern: r"-----BEGIN (RSA|DSA|EC|OPENSSH) PRIVATE KEY-----(?s:.*?-----END (RSA|DSA|EC|OPENSSH) PRIVATE KEY-----)?",
+        pattern_type: "ssh_key",
+        severity: "high",
+        description: "SSH private key detected",
+        label: "PRIVATE_KEY",
+    },
+    Detector {
+        pattern: r"AKIA[0-9A-Z]{16}",
+        pattern_type: "aws_key",
+        severity: "high",
+        description: "AWS Access Key detected",
+        label: "AWS_KEY",
+    },
+    Detector {
+        pattern: r"(?i)(jdbc|mongodb|postgres|mysql)://[^\s]+",
+        pattern_type: "connection_string",
+        severity: "high",
+        description: "Database connection string detected",
+        label: "CONNECTION_STRING",
+    },
+    Detector {
+        pattern: r"(?i)(password|passwd|pwd|secret|api[_-]?key|token)\s*[:=]\s*(?P<value>\S+)",
+        pattern_type: "credentials",
+        severity: "high",
+        description: "Password or secret detected",
+        label: "SECRET",
+    },
+    Detector {
+        pattern: r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+        pattern_type: "email",
+        severity: "medium",
+        description: "Email address detected",
+        label: "EMAIL",
+    },
+    Detector {
+        pattern: r"\b(10\.\d{1,3}\.\d{1,3}\.\d{1,3}|172\.(1[6-9]|2\d|3[01])\.\d{1,3}\.\d{1,3}|192\.168\.\d{1,3}\.\d{1,3})\b",
+        pattern_type: "internal_ip",
+        severity: "medium",
+        description: "Internal IP address detected",
+        label: "IP",
+    },
+];
 
 pub fn scan(text: &str) -> Vec<FlaggedSection> {
     let mut flags = Vec::new();
 
-    // Pattern definitions with severity
-    let patterns = vec![
-        (
-            r"AKIA[0-9A-Z]{16}",
-            "aws_key",
-            "high",
-            "AWS Access Key detected",
-        ),
-        (
-            r"(?i)(password|passwd|pwd|secret|api[_-]?key|token)\s*[:=]\s*\S+",
-            "credentials",
-            "high",
-            "Password or secret detected",
-        ),
-        (
-            r"\b(10\.\d{1,3}\.\d{1,3}\.\d{1,3}|172\.(1[6-9]|2\d|3[01])\.\d{1,3}\.\d{1,3}|192\.168\.\d{1,3}\.\d{1,3})\b",
-            "internal_ip",
-            "medium",
-            "Internal IP address detected",
-        ),
-        (
-            r"-----BEGIN (RSA|DSA|EC|OPENSSH) PRIVATE KEY-----",
-            "ssh_key",
-            "high",
-            "SSH private key detected",
-        ),
-        (
-            r"(?i)(jdbc|mongodb|postgres|mysql)://[^\s]+",
-            "connection_string",
-            "high",
-            "Database connection string detected",
-        ),
-    ];
-
-    for (pattern_str, pattern_type, severity, description) in patterns {
-        let re = Regex::new(pattern_str).unwrap();
+    for detector in DETECTORS {
+        let re = Regex::new(detector.pattern).unwrap();
         for (line_num, line) in text.lines().enumerate() {
             if let Some(mat) = re.find(line) {
                 let matched_text = mat.as_str();
@@ -51,11 +76,11 @@ pub fn scan(text: &str) -> Vec<FlaggedSection> {
                 };
 
                 // Log description for debugging
-                log::debug!("Sensitive data detected: {} at line {}", description, line_num + 1);
+                log::debug!("Sensitive data detected: {} at line {}", detector.description, line_num + 1);
 
                 flags.push(FlaggedSection {
-                    pattern_type: pattern_type.to_string(),
-                    severity: severity.to_string(),
+                    pattern_type: detector.pattern_type.to_string(),
+                    severity: detector.severity.to_string(),
                     matched_text: truncated,
                     line_number: line_num + 1, // 1-indexed for user display
                     start_col: mat.start(),
@@ -68,6 +93,97 @@ pub fn scan(text: &str) -> Vec<FlaggedSection> {
     flags
 }
 
+/// Copies of `tickets` with sensitive data in the summary, description and
+/// comments replaced by placeholders, along with what each one stands for.
+/// A value gets the same placeholder everywhere it appears, across tickets.
+pub fn redact_tickets(tickets: &[JiraTicket]) -> (Vec<JiraTicket>, Vec<Redaction>) {
+    redact_tickets_with(tickets, &[])
+}
+
+/// Like [`redact_tickets`], continuing from the `known` redactions of an
+/// earlier pass: values in there keep their placeholder, new ones are
+/// numbered after them. The returned list starts with `known`.
+pub fn redact_tickets_with(
+    tickets: &[JiraTicket],
+    known: &[Redaction],
+) -> (Vec<JiraTicket>, Vec<Redaction>) {
+    let mut redactions = known.to_vec();
+    let redacted = tickets
+        .iter()
+        .map(|ticket| {
+            let mut ticket = ticket.clone();
+            ticket.summary = redact(&ticket.summary, &mut redactions);
+            ticket.description = ticket.description.as_deref().map(|d| redact(d, &mut redactions));
+            for comment in &mut ticket.comments {
+                comment.body = redact(&comment.body, &mut redactions);
+            }
+            for link in &mut ticket.linked_issues {
+                link.summary = redact(&link.summary, &mut redactions);
+            }
+            // Comment events carry the comment body
+            for event in &mut ticket.timeline {
+                event.to = event.to.as_deref().map(|t| redact(t, &mut redactions));
+            }
+            ticket
+        })
+        .collect();
+    (redacted, redactions)
+}
+
+/// Replace sensitive data in `text` with placeholders like `<IP_1>`. Values
+/// already in `redactions` reuse their placeholder; new ones are added.
+pub fn redact(text: &str, redactions: &mut Vec<Redaction>) -> String {
+    let placeholder_re = Regex::new(r"^<[A-Z_]+_\d+>$").unwrap();
+    let mut text = text.to_string();
+
+    for detector in DETECTORS {
+        let re = Regex::new(detector.pattern).unwrap();
+        text = re
+            .replace_all(&text, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                let value = caps.name("value").unwrap_or(whole);
+                if placeholder_re.is_match(value.as_str()) {
+                    return whole.as_str().to_string();
+                }
+                let placeholder = placeholder_for(detector, value.as_str(), redactions);
+                format!(
+                    "{}{}{}",
+                    &text[whole.start()..value.start()],
+                    placeholder,
+                    &text[value.end()..whole.end()]
+                )
+            })
+            .into_owned();
+    }
+
+    text
+}
+
+fn placeholder_for(detector: &Detector, value: &str, redactions: &mut Vec<Redaction>) -> String {
+    if let Some(existing) = redactions.iter().find(|r| r.value == value) {
+        return existing.placeholder.clone();
+    }
+    let number = redactions
+        .iter()
+        .filter(|r| r.pattern_type == detector.pattern_type)
+        .count()
+        + 1;
+    let placeholder = format!("<{}_{}>", detector.label, number);
+    redactions.push(Redaction {
+        placeholder: placeholder.clone(),
+        pattern_type: detector.pattern_type.to_string(),
+        value: value.to_string(),
+    });
+    placeholder
+}
+
+/// Put the real values back in place of the placeholders in `text`
+pub fn restore(text: &str, redactions: &[Redaction]) -> String {
+    redactions.iter().fold(text.to_string(), |text, redaction| {
+        text.replace(&redaction.placeholder, &redaction.value)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,4 +237,85 @@ mod tests {
         assert!(flags[0].matched_text.len() <= 53); // 50 + "..."
         assert!(flags[0].matched_text.ends_with("..."));
     }
+
+    #[test]
+    fn test_email_detection() {
+        let flags = scan("Reported by jane.doe@example.com yesterday");
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].pattern_type, "email");
+    }
+
+    #[test]
+    fn test_redact_uses_stable_placeholders() {
+        let mut redactions = Vec::new();
+        let text = "Ping 10.0.0.5 then 10.0.0.6, mail ops@example.com. Retry 10.0.0.5.";
+        let redacted = redact(text, &mut redactions);
+
+        assert_eq!(
+            redacted,
+            "Ping <IP_1> then <IP_2>, mail <EMAIL_1>. Retry <IP_1>."
+        );
+        assert_eq!(redactions.len(), 3);
+        assert_eq!(restore(&redacted, &redactions), text);
+    }
+
+    #[test]
+    fn test_redact_keeps_credential_names() {
+        let mut redactions = Vec::new();
+        let redacted = redact(
+            "password: hunter2\nurl postgres://app:pw@10.1.2.3/db",
+            &mut redactions,
+        );
+
+        assert_eq!(redacted, "password: <SECRET_1>\nurl <CONNECTION_STRING_1>");
+        assert_eq!(redactions[1].value, "hunter2");
+        assert!(!redactions.iter().any(|r| r.pattern_type == "internal_ip"));
+    }
+
+    #[test]
+    fn test_redact_tickets_shares_placeholders() {
+        let ticket = |key: &str, description: &str| JiraTicket {
+            key: key.to_string(),
+            summary: "VPN fails".to_string(),
+            description: Some(description.to_string()),
+            status: "Resolved".to_string(),
+            priority: None,
+            resolution: None,
+            labels: vec![],
+            components: vec![],
+            comments: vec![crate::models::JiraComment {
+                author: "Agent".to_string(),
+                body: "Gateway is 192.168.0.1".to_string(),
+                created: "2024-01-01".to_string(),
+            }],
+            linked_issues: vec![],
+            timeline: vec![crate::models::TimelineEvent {
+                timestamp: "2024-01-01".to_string(),
+                author: "Agent".to_string(),
+                kind: crate::models::TimelineEventKind::Comment,
+                from: None,
+                to: Some("Gateway is 192.168.0.1".to_string()),
+            }],
+            created: "2024-01-01".to_string(),
+            updated: "2024-01-01".to_string(),
+        };
+        let tickets = [
+            ticket("SUP-1", "User bob@example.com on 192.168.0.1"),
+            ticket("SUP-2", "Same for bob@example.com"),
+        ];
+
+        let (redacted, redactions) = redact_tickets(&tickets);
+        assert_eq!(
+            redacted[0].description.as_deref(),
+            Some("User <EMAIL_1> on <IP_1>")
+        );
+        assert_eq!(redacted[1].description.as_deref(), Some("Same for <EMAIL_1>"));
+        assert_eq!(redacted[1].comments[0].body, "Gateway is <IP_1>");
+        assert_eq!(
+            redacted[1].timeline[0].to.as_deref(),
+            Some("Gateway is <IP_1>")
+        );
+        assert_eq!(redactions.len(), 2);
+        assert_eq!(tickets[0].comments[0].body, "Gateway is 192.168.0.1");
+    }
 }
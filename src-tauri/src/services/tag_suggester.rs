@@ -0,0 +1,294 @@
+use crate::models::{JiraTicket, TagSource, TagSuggestion};
+use std::collections::HashMap;
+
+/// Suggestions returned when the caller does not ask for a number
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 8;
+
+/// Confluence rejects labels longer than this
+const MAX_LABEL_LENGTH: usize = 255;
+
+/// Keywords have to appear this often in the article to be suggested
+const MIN_KEYWORD_COUNT: usize = 2;
+
+/// Base score per source, before boosts
+const JIRA_LABEL_SCORE: f32 = 1.0;
+const COMPONENT_SCORE: f32 = 0.9;
+const KEYWORD_MAX_SCORE: f32 = 0.6;
+const EXISTING_MENTION_SCORE: f32 = 0.4;
+
+/// Added to any candidate that is already used as a tag elsewhere, so the
+/// knowledge base converges on one vocabulary
+const EXISTING_TAG_BOOST: f32 = 0.3;
+
+/// Words too common in support articles to say anything about the topic
+pub(crate) const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "also", "because", "been", "before", "being",
+    "below", "between", "both", "cannot", "could", "does", "doing", "down", "during", "each",
+    "error", "every", "expected", "file", "fixed", "from", "further", "have", "having", "here",
+    "into", "issue", "itself", "just", "like", "make", "more", "most", "must", "need", "needs",
+    "note", "notes", "once", "only", "other", "over", "please", "problem", "result", "same",
+    "should", "shows", "since", "solution", "some", "step", "steps", "such", "than", "that",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "ticket",
+    "under", "until", "used", "user", "users", "using", "very", "want", "were", "what", "when",
+    "where", "which", "while", "will", "with", "without", "work", "works", "would", "your",
+];
+
+/// Turn free text into a valid Confluence label: lowercase, no whitespace,
+/// none of the characters Confluence rejects. Returns `None` if nothing is left.
+pub fn normalize_label(text: &str) -> Option<String> {
+    let mut label = String::new();
+    for c in text.trim().chars().flat_map(char::to_lowercase) {
+        let c = if c.is_whitespace() || "!#&()*,.:;<>?@[]^|~/\\\"'`".contains(c) {
+            '-'
+        } else {
+            c
+        };
+        if c == '-' && (label.is_empty() || label.ends_with('-')) {
+            continue;
+        }
+        label.push(c);
+    }
+
+    let label = label.trim_end_matches('-');
+    let label: String = label.chars().take(MAX_LABEL_LENGTH).collect();
+    if label.is_empty() {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+/// Normalize a list of tags, dropping empty and duplicate ones
+pub fn normalize_labels(tags: &[String]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for label in tags.iter().filter_map(|t| normalize_label(t)) {
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    labels
+}
+
+/// Rank tag suggestions for an article from the Jira labels and components
+/// of its tickets, recurring keywords of its body, and `existing_tags` (tag
+/// and number of articles using it) already in the knowledge base.
+pub fn suggest(
+    tickets: &[JiraTicket],
+    markdown: &str,
+    existing_tags: &[(String, usize)],
+    limit: usize,
+) -> Vec<TagSuggestion> {
+    let mut candidates: HashMap<String, Candidate> = HashMap::new();
+
+    for ticket in tickets {
+        for label in &ticket.labels {
+            add(
+                &mut candidates,
+                label,
+                JIRA_LABEL_SCORE,
+                TagSource::JiraLabel,
+            );
+        }
+        for component in &ticket.components {
+            add(
+                &mut candidates,
+                component,
+                COMPONENT_SCORE,
+                TagSource::Component,
+            );
+        }
+    }
+
+    let keywords = keyword_counts(markdown);
+    let max_count = keywords.values().copied().max().unwrap_or(0);
+    for (word, count) in &keywords {
+        if *count >= MIN_KEYWORD_COUNT {
+            let score = KEYWORD_MAX_SCORE * *count as f32 / max_count as f32;
+            add(&mut candidates, word, score, TagSource::Keyword);
+        }
+    }
+
+    // Existing tags mentioned in the article are candidates in their own right
+    let text = markdown.to_lowercase();
+    for (tag, _) in existing_tags {
+        if tag.len() >= 3 && (text.contains(tag.as_str()) || text.contains(&tag.replace('-', " ")))
+        {
+            add(
+                &mut candidates,
+                tag,
+                EXISTING_MENTION_SCORE,
+                TagSource::Existing,
+            );
+        }
+    }
+
+    let existing: HashMap<&str, usize> = existing_tags
+        .iter()
+        .map(|(tag, count)| (tag.as_str(), *count))
+        .collect();
+
+    let mut suggestions: Vec<TagSuggestion> = candidates
+        .into_iter()
+        .map(|(tag, mut candidate)| {
+            if existing.contains_key(tag.as_str()) {
+                candidate.score += EXISTING_TAG_BOOST;
+                if !candidate.sources.contains(&TagSource::Existing) {
+                    candidate.sources.push(TagSource::Existing);
+                }
+            }
+            TagSuggestion {
+                tag,
+                score: candidate.score,
+                sources: candidate.sources,
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| {
+                let a_uses = existing.get(a.tag.as_str()).unwrap_or(&0);
+                let b_uses = existing.get(b.tag.as_str()).unwrap_or(&0);
+                b_uses.cmp(a_uses)
+            })
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+fn add(candidates: &mut HashMap<String, Candidate>, text: &str, score: f32, source: TagSource) {
+    if let Some(tag) = normalize_label(text) {
+        candidates.entry(tag).or_default().add(score, source);
+    }
+}
+
+#[derive(Default)]
+struct Candidate {
+    score: f32,
+    sources: Vec<TagSource>,
+}
+
+impl Candidate {
+    /// Each source counts once, with its best score
+    fn add(&mut self, score: f32, source: TagSource) {
+        if !self.sources.contains(&source) {
+            self.sources.push(source);
+            self.score += score;
+        }
+    }
+}
+
+/// How often each candidate keyword occurs in the article. Code blocks are
+/// skipped; they are full of identifiers, not topics.
+fn keyword_counts(markdown: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    let mut in_code_block = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        for word in line.split(|c: char| !(c.is_alphanumeric() || c == '-')) {
+            let word = word.trim_matches('-').to_lowercase();
+            if word.len() < 4
+                || word.len() > 30
+                || word.chars().all(|c| c.is_ascii_digit() || c == '-')
+                || STOPWORDS.contains(&word.as_str())
+            {
+                continue;
+            }
+            *counts.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket() -> JiraTicket {
+        JiraTicket {
+            key: "SUP-1".to_string(),
+            summary: "VPN drops every 5 minutes".to_string(),
+            description: None,
+            status: "Resolved".to_string(),
+            priority: None,
+            resolution: Some("Fixed".to_string()),
+            labels: vec!["VPN".to_string(), "Remote Access".to_string()],
+            components: vec!["Network Infrastructure".to_string()],
+            comments: vec![],
+            linked_issues: vec![],
+            timeline: vec![],
+            created: "2024-01-01T09:00:00".to_string(),
+            updated: "2024-01-01T12:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(
+            normalize_label("Remote Access").as_deref(),
+            Some("remote-access")
+        );
+        assert_eq!(normalize_label("  C#/.NET  ").as_deref(), Some("c-net"));
+        assert_eq!(normalize_label("sso:okta").as_deref(), Some("sso-okta"));
+        assert_eq!(
+            normalize_label("already_fine").as_deref(),
+            Some("already_fine")
+        );
+        assert_eq!(normalize_label(" ?! "), None);
+    }
+
+    #[test]
+    fn test_normalize_labels_dedupes() {
+        let tags = vec!["VPN".to_string(), "vpn ".to_string(), "".to_string()];
+        assert_eq!(normalize_labels(&tags), vec!["vpn"]);
+    }
+
+    #[test]
+    fn test_suggest_ranks_jira_metadata_first() {
+        let markdown = "# VPN drops\n\n## Problem\nThe tunnel resets. Keepalive packets are lost.\n\n## Resolution\nRaise the keepalive interval so the tunnel stays up.\n```\nset keepalive 30\nset keepalive 60\n```";
+        let suggestions = suggest(&[ticket()], markdown, &[], 10);
+
+        let tags: Vec<&str> = suggestions.iter().map(|s| s.tag.as_str()).collect();
+        assert_eq!(
+            &tags[..3],
+            &["remote-access", "vpn", "network-infrastructure"]
+        );
+        assert!(tags.contains(&"keepalive"));
+        assert!(tags.contains(&"tunnel"));
+        // Stopwords and one-off words are not suggested
+        assert!(!tags.contains(&"problem"));
+        assert!(!tags.contains(&"interval"));
+    }
+
+    #[test]
+    fn test_suggest_prefers_existing_vocabulary() {
+        let markdown = "Tunnel resets. The tunnel drops. Keepalive is lost, keepalive again.";
+        let existing = vec![("keepalive".to_string(), 12), ("wireguard".to_string(), 3)];
+        let suggestions = suggest(&[], markdown, &existing, 10);
+
+        assert_eq!(suggestions[0].tag, "keepalive");
+        assert_eq!(
+            suggestions[0].sources,
+            vec![TagSource::Keyword, TagSource::Existing]
+        );
+        // Existing tags the article never mentions are not suggested
+        assert!(!suggestions.iter().any(|s| s.tag == "wireguard"));
+    }
+
+    #[test]
+    fn test_suggest_respects_limit() {
+        let suggestions = suggest(&[ticket()], "", &[], 2);
+        assert_eq!(suggestions.len(), 2);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::models::{JiraComment, TimelineEvent, TimelineEventKind};
+use chrono::{DateTime, FixedOffset};
+
+/// Characters of a comment shown in the timeline; the full text is in the
+/// ticket's comments
+const COMMENT_PREVIEW_CHARS: usize = 160;
+
+/// The timeline event kind a changelog field is recorded as. Other fields
+/// (labels, descriptions, sprints, ...) say little about how an incident
+/// unfolded and are left out.
+pub fn field_kind(field: &str) -> Option<TimelineEventKind> {
+    match field.to_lowercase().as_str() {
+        "status" => Some(TimelineEventKind::Status),
+        "assignee" => Some(TimelineEventKind::Assignee),
+        "priority" => Some(TimelineEventKind::Priority),
+        "resolution" => Some(TimelineEventKind::Resolution),
+        _ => None,
+    }
+}
+
+/// Merge the creation of a ticket, its field changes and its comments into
+/// one timeline, oldest first. Events at the same time keep the order they
+/// were given in: creation, changes, comments.
+pub fn build(
+    created: &str,
+    reporter: &str,
+    changes: Vec<TimelineEvent>,
+    comments: &[JiraComment],
+) -> Vec<TimelineEvent> {
+    let mut events = Vec::with_capacity(changes.len() + comments.len() + 1);
+    if !created.is_empty() {
+        events.push(TimelineEvent {
+            timestamp: created.to_string(),
+            author: reporter.to_string(),
+            kind: TimelineEventKind::Created,
+            from: None,
+            to: None,
+        });
+    }
+    events.extend(changes);
+    events.extend(comments.iter().map(|comment| TimelineEvent {
+        timestamp: comment.created.clone(),
+        author: comment.author.clone(),
+        kind: TimelineEventKind::Comment,
+        from: None,
+        to: Some(comment.body.clone()),
+    }));
+
+    // Timestamps may carry different offsets, so compare them as instants
+    events.sort_by(|a, b| match (parse(&a.timestamp), parse(&b.timestamp)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.timestamp.cmp(&b.timestamp),
+    });
+    events
+}
+
+/// The timeline as one line per event, for the drafting prompt
+pub fn format(timeline: &[TimelineEvent]) -> String {
+    timeline
+        .iter()
+        .map(|event| {
+            format!(
+                "- {} {}: {}",
+                format_time(&event.timestamp),
+                event.author,
+                describe(event)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe(event: &TimelineEvent) -> String {
+    let value = |v: &Option<String>| match v.as_deref() {
+        Some(v) if !v.is_empty() => v.to_string(),
+        _ => "none".to_string(),
+    };
+    let change = |field: &str| format!("{} {} -> {}", field, value(&event.from), value(&event.to));
+
+    match event.kind {
+        TimelineEventKind::Created => "created the ticket".to_string(),
+        TimelineEventKind::Status => change("status"),
+        TimelineEventKind::Assignee => change("assignee"),
+        TimelineEventKind::Priority => change("priority"),
+        TimelineEventKind::Resolution => change("resolution"),
+        TimelineEventKind::Comment => {
+            let text = event
+                .to
+                .as_deref()
+                .unwrap_or("")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if text.chars().count() > COMMENT_PREVIEW_CHARS {
+                let cut: String = text.chars().take(COMMENT_PREVIEW_CHARS).collect();
+                format!("commented \"{}...\"", cut.trim_end())
+            } else {
+                format!("commented \"{}\"", text)
+            }
+        }
+    }
+}
+
+/// Jira's timestamps, e.g. "2024-01-01T10:00:00.000+0000"
+fn parse(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(timestamp))
+        .ok()
+}
+
+/// "2024-01-01 10:00 UTC", or the timestamp as given when it cannot be read
+fn format_time(timestamp: &str) -> String {
+    match parse(timestamp) {
+        Some(time) => time
+            .with_timezone(&chrono::Utc)
+            .format("%Y-%m-%d %H:%M UTC")
+            .to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(timestamp: &str, kind: TimelineEventKind, from: &str, to: &str) -> TimelineEvent {
+        TimelineEvent {
+            timestamp: timestamp.to_string(),
+            author: "Dana".to_string(),
+            kind,
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_orders_events_across_offsets() {
+        let comments = vec![JiraComment {
+            author: "Sam".to_string(),
+            body: "Mail queue is backing up".to_string(),
+            created: "2024-03-01T10:05:00.000+0100".to_string(),
+        }];
+        let changes = vec![
+            change(
+                "2024-03-01T09:30:00.000+0000",
+                TimelineEventKind::Priority,
+                "Medium",
+                "Critical",
+            ),
+            change(
+                "2024-03-01T08:50:00.000+0000",
+                TimelineEventKind::Status,
+                "Open",
+                "In Progress",
+            ),
+        ];
+
+        let timeline = build("2024-03-01T08:45:00.000+0000", "Alex", changes, &comments);
+
+        let kinds: Vec<TimelineEventKind> = timeline.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TimelineEventKind::Created,
+                TimelineEventKind::Status,
+                TimelineEventKind::Comment,
+                TimelineEventKind::Priority,
+            ]
+        );
+        assert_eq!(timeline[0].author, "Alex");
+    }
+
+    #[test]
+    fn test_format() {
+        let timeline = vec![
+            change(
+                "2024-03-01T10:30:00.000+0100",
+                TimelineEventKind::Assignee,
+                "",
+                "Dana",
+            ),
+            TimelineEvent {
+                timestamp: "2024-03-01T09:40:00.000+0000".to_string(),
+                author: "Sam".to_string(),
+                kind: TimelineEventKind::Comment,
+                from: None,
+                to: Some(format!("Restarted\n{}", "the relay ".repeat(30))),
+            },
+        ];
+
+        let text = format(&timeline);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "- 2024-03-01 09:30 UTC Dana: assignee none -> Dana"
+        );
+        assert!(lines[1].starts_with("- 2024-03-01 09:40 UTC Sam: commented \"Restarted the relay"));
+        assert!(lines[1].ends_with("...\""));
+    }
+
+    #[test]
+    fn test_field_kind() {
+        assert_eq!(field_kind("status"), Some(TimelineEventKind::Status));
+        assert_eq!(field_kind("Priority"), Some(TimelineEventKind::Priority));
+        assert_eq!(field_kind("labels"), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::AppError;
+use crate::models::Credentials;
 use keyring::Entry;
 
 const SERVICE_PREFIX: &str = "kb-drafter";
@@ -36,10 +37,51 @@ pub fn delete_token(service: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Store Jira or Confluence credentials, as JSON, under `service`
+pub fn store_credentials(service: &str, credentials: &Credentials) -> Result<(), AppError> {
+    let json = serde_json::to_string(credentials)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize credentials: {}", e)))?;
+    store_token(service, &json)
+}
+
+/// Jira or Confluence credentials stored under `service`. A bare token,
+/// as saved before Cloud support, is read as a PAT.
+pub fn get_credentials(service: &str) -> Result<Credentials, AppError> {
+    let stored = get_token(service)?;
+    Ok(parse_credentials(&stored))
+}
+
+fn parse_credentials(stored: &str) -> Credentials {
+    serde_json::from_str(stored).unwrap_or_else(|_| Credentials::Pat {
+        token: stored.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_credentials() {
+        let basic = Credentials::Basic {
+            email: "me@acme.com".to_string(),
+            token: "api-token".to_string(),
+        };
+        let json = serde_json::to_string(&basic).unwrap();
+        assert_eq!(
+            json,
+            r#"{"mode":"basic","email":"me@acme.com","token":"api-token"}"#
+        );
+        assert!(parse_credentials(&json) == basic);
+
+        // Tokens saved by earlier versions
+        assert!(
+            parse_credentials("NjE2ODk0") == Credentials::Pat {
+                token: "NjE2ODk0".to_string()
+            }
+        );
+    }
+
     #[test]
     #[ignore] // Keychain access is unreliable in test environments
     fn test_token_storage_roundtrip() {
@@ -0,0 +1,417 @@
+use crate::error::AppError;
+use crate::models::{
+    ArticleDraft, ArticleField, Language, NewArticle, NewDraftProvenance, DEFAULT_LANGUAGE,
+};
+use crate::services::article_fields::{self, ArticleFields};
+use crate::services::drafter;
+use crate::services::llm::{LlmProvider, TokenCallback};
+use crate::services::sections;
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// Languages articles can be drafted or translated into, by ISO 639-1 code
+const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("de", "German"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("it", "Italian"),
+    ("nl", "Dutch"),
+    ("pt", "Portuguese"),
+];
+
+const TRANSLATION_SYSTEM_PROMPT: &str = "You are a professional translator of IT support \
+documentation. You translate Knowledge Base articles faithfully, using the terms support \
+engineers in the target language actually use.";
+
+/// Plain text the model must copy unchanged: URLs and file paths. Code
+/// blocks and inline code are found by the markdown parser.
+const VERBATIM_PATTERNS: &[&str] = &[
+    r"https?://[^\s)>\]]+",
+    r"\b[A-Za-z]:\\[^\s`]*",
+    r"(?:^|[\s(])(~?/[\w.-]+(?:/[\w.-]+)+/?)",
+];
+
+pub fn languages() -> Vec<Language> {
+    LANGUAGES
+        .iter()
+        .map(|(code, name)| Language {
+            code: code.to_string(),
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// The English name of a supported language code
+pub fn language_name(code: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, name)| *name)
+}
+
+/// Confluence page title of an article. Page titles are unique per space,
+/// so articles not in the default language get their code appended, e.g.
+/// "VPN bricht ab (DE)".
+pub fn page_title(title: &str, language: &str) -> String {
+    if language.eq_ignore_ascii_case(DEFAULT_LANGUAGE) {
+        title.to_string()
+    } else {
+        format!("{} ({})", title, language.to_uppercase())
+    }
+}
+
+/// Confluence labels of an article: its tags, plus `lang-<code>` for
+/// articles not in the default language so each team can filter on theirs
+pub fn page_labels(tags: &[String], language: &str) -> Vec<String> {
+    let mut labels = tags.to_vec();
+    if !language.eq_ignore_ascii_case(DEFAULT_LANGUAGE) {
+        labels.push(format!("lang-{}", language.to_lowercase()));
+    }
+    labels
+}
+
+fn placeholder(index: usize) -> String {
+    format!("[[KEEP_{}]]", index + 1)
+}
+
+/// Replace code blocks, inline code, URLs and paths in `markdown` with
+/// `[[KEEP_n]]` placeholders. Returns the text to translate and the
+/// originals, in placeholder order.
+pub fn protect(markdown: &str) -> (String, Vec<String>) {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut code_block: Option<usize> = None;
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code_block = Some(range.start),
+            Event::End(pulldown_cmark::TagEnd::CodeBlock) => {
+                if let Some(start) = code_block.take() {
+                    ranges.push((start, range.end));
+                }
+            }
+            Event::Code(_) if code_block.is_none() => ranges.push((range.start, range.end)),
+            _ => {}
+        }
+    }
+
+    for pattern in VERBATIM_PATTERNS {
+        let re = Regex::new(pattern).unwrap();
+        for caps in re.captures_iter(markdown) {
+            let m = caps.get(1).or_else(|| caps.get(0)).unwrap();
+            if !ranges.iter().any(|(s, e)| m.start() < *e && *s < m.end()) {
+                ranges.push((m.start(), m.end()));
+            }
+        }
+    }
+    ranges.sort();
+
+    let mut text = String::with_capacity(markdown.len());
+    let mut kept = Vec::with_capacity(ranges.len());
+    let mut last = 0;
+    for (start, end) in ranges {
+        // Code blocks end with their newline; keep it outside the placeholder
+        let end = if markdown[start..end].ends_with('\n') {
+            end - 1
+        } else {
+            end
+        };
+        text.push_str(&markdown[last..start]);
+        text.push_str(&placeholder(kept.len()));
+        kept.push(markdown[start..end].to_string());
+        last = end;
+    }
+    text.push_str(&markdown[last..]);
+
+    (text, kept)
+}
+
+/// Put the protected originals back. Fails if the model dropped or
+/// duplicated a placeholder, since a command or code block would then be
+/// missing or wrong.
+pub fn restore(translated: &str, kept: &[String]) -> Result<String, AppError> {
+    let lost = (0..kept.len())
+        .filter(|i| translated.matches(&placeholder(*i)).count() != 1)
+        .count();
+    if lost > 0 {
+        return Err(AppError::Internal(format!(
+            "The translation lost {} code blocks, commands or paths. Try again.",
+            lost
+        )));
+    }
+
+    // Back to front, so [[KEEP_1]] is not replaced inside [[KEEP_10]]
+    Ok(kept
+        .iter()
+        .enumerate()
+        .rev()
+        .fold(translated.to_string(), |text, (i, original)| {
+            text.replace(&placeholder(i), original)
+        }))
+}
+
+/// Build the prompts for translating protected markdown (see [`protect`])
+pub fn build_translation_prompt(protected_markdown: &str, language: &str) -> (String, String) {
+    let user_prompt = format!(
+        r#"Translate this Knowledge Base article into {language}.
+
+Rules:
+- Translate the title, the section headings and the text. Keep every heading at its level (same number of #), in the same order; do not add, merge or drop sections.
+- Copy every placeholder like [[KEEP_1]] exactly once, unchanged, where it belongs in the sentence. They stand for code, commands, paths and URLs.
+- Keep the markdown formatting: lists, numbering, tables, bold and links.
+- Keep product names, UI labels in quotes and error messages as they are.
+- Return only the translated article.
+
+===== ARTICLE =====
+{protected_markdown}
+===== END OF ARTICLE =====
+Translate the article above into {language}, following the rules."#
+    );
+
+    (TRANSLATION_SYSTEM_PROMPT.to_string(), user_prompt)
+}
+
+/// `translated` with its section headings replaced by those of `source`,
+/// for mapping sections to fields by the original names. The title is left
+/// as it is. `None` when the section structure differs.
+pub fn align_headings(translated: &str, source: &str) -> Option<String> {
+    let translated_sections: Vec<_> = sections::parse_sections(translated)
+        .into_iter()
+        .filter(|s| s.level > 1)
+        .collect();
+    let source_sections: Vec<_> = sections::parse_sections(source)
+        .into_iter()
+        .filter(|s| s.level > 1)
+        .collect();
+    let same_structure = translated_sections.len() == source_sections.len()
+        && translated_sections
+            .iter()
+            .zip(&source_sections)
+            .all(|(t, s)| t.level == s.level);
+    if !same_structure {
+        return None;
+    }
+
+    let mut aligned = translated.to_string();
+    for (t, s) in translated_sections.iter().zip(&source_sections).rev() {
+        aligned.replace_range(
+            t.start..t.body_start,
+            &format!("{} {}\n", "#".repeat(t.level as usize), s.heading),
+        );
+    }
+    Some(aligned)
+}
+
+/// The fields of a translated article, found through the section headings
+/// of the article it was translated from
+pub fn fields_from_translation(
+    translated: &str,
+    source: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+) -> Option<ArticleFields> {
+    let aligned = align_headings(translated, source)?;
+    let mut fields = article_fields::fields_from_markdown(&aligned, mapping);
+    fields.title = article_fields::fields_from_markdown(translated, mapping).title;
+    Some(fields)
+}
+
+/// Re-derive the fields of a translation after its markdown was edited,
+/// like `article_fields::sync_fields`. Falls back to the heading names when
+/// the sections no longer match the source article.
+pub fn sync_fields(
+    article: &mut NewArticle,
+    source: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+) -> bool {
+    let Some(fields) = fields_from_translation(&article.content_markdown, source, mapping) else {
+        return false;
+    };
+    if !fields.title.is_empty() {
+        article.title = fields.title;
+    }
+    article.problem = fields.problem;
+    article.solution = fields.solution;
+    article.expected_result = fields.expected_result;
+    article.prerequisites = fields.prerequisites;
+    article.additional_notes = fields.additional_notes;
+    true
+}
+
+/// Translate an article's markdown into `language` (an ISO 639-1 code).
+/// Code, commands, paths and URLs are kept out of the model's hands, and
+/// the result must have the same sections as the original so its fields
+/// can be filled in.
+///
+/// The provenance record has the translation prompt and output; the source
+/// tickets, template and redactions are the caller's to fill in.
+pub async fn translate(
+    markdown: &str,
+    tags: &[String],
+    language: &str,
+    mapping: &BTreeMap<String, ArticleField>,
+    provider: &dyn LlmProvider,
+    on_token: TokenCallback<'_>,
+) -> Result<(ArticleDraft, NewDraftProvenance), AppError> {
+    let name = language_name(language)
+        .ok_or_else(|| AppError::Internal(format!("Unsupported language: {}", language)))?;
+
+    let (protected, kept) = protect(markdown);
+    let (system_prompt, user_prompt) = build_translation_prompt(&protected, name);
+    log::info!(
+        "Translating article into {} with {} ({} protected spans)",
+        name,
+        provider.name(),
+        kept.len()
+    );
+
+    let started = Instant::now();
+    let raw_output = provider
+        .generate(&system_prompt, &user_prompt, on_token)
+        .await?;
+    let duration = started.elapsed();
+    let translated = restore(&drafter::post_process(&raw_output), &kept)?;
+
+    let fields = fields_from_translation(&translated, markdown, mapping).ok_or_else(|| {
+        AppError::Internal("The translation changed the article's sections. Try again.".to_string())
+    })?;
+
+    let mut draft = article_fields::into_draft(fields, tags.to_vec(), translated, false);
+    draft.language = Some(language.to_lowercase());
+
+    let usage = provider.last_usage().unwrap_or_default();
+    let provenance = NewDraftProvenance {
+        ticket_keys: vec![],
+        template_id: None,
+        provider: provider.name().to_string(),
+        model: provider.model().to_string(),
+        options: provider.generation_settings(),
+        system_prompt,
+        user_prompt,
+        references: vec![],
+        redactions: vec![],
+        warnings: vec![],
+        raw_output,
+        structured: false,
+        prompt_eval_count: usage.prompt_eval_count,
+        eval_count: usage.eval_count,
+        duration_ms: duration.as_millis() as u64,
+    };
+
+    Ok((draft, provenance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm::test_provider::FixedProvider;
+
+    const SOURCE: &str = "# Printer offline\n\n## Problem\nThe printer at https://print.example.com/q shows offline.\n\n## Solution\n1. Run `lpstat -p` and check /etc/cups/printers.conf\n2. Restart the spooler:\n\n```bash\nsudo systemctl restart cups\n```\n\n## Expected Result\nThe printer is online.\n";
+
+    #[test]
+    fn test_protect_and_restore_round_trip() {
+        let (protected, kept) = protect(SOURCE);
+
+        assert_eq!(
+            kept,
+            vec![
+                "https://print.example.com/q",
+                "`lpstat -p`",
+                "/etc/cups/printers.conf",
+                "```bash\nsudo systemctl restart cups\n```",
+            ]
+        );
+        assert!(protected.contains("Run [[KEEP_2]] and check [[KEEP_3]]"));
+        assert!(!protected.contains("systemctl"));
+        assert_eq!(restore(&protected, &kept).unwrap(), SOURCE);
+    }
+
+    #[test]
+    fn test_translations_get_their_own_page() {
+        assert_eq!(page_title("VPN drops", "en"), "VPN drops");
+        assert_eq!(page_title("VPN bricht ab", "de"), "VPN bricht ab (DE)");
+        assert_eq!(page_labels(&["vpn".to_string()], "en"), vec!["vpn"]);
+        assert_eq!(
+            page_labels(&["vpn".to_string()], "es"),
+            vec!["vpn", "lang-es"]
+        );
+    }
+
+    #[test]
+    fn test_restore_rejects_lost_placeholders() {
+        let kept = vec!["`a`".to_string(), "`b`".to_string()];
+        assert!(restore("only [[KEEP_1]]", &kept).is_err());
+        assert!(restore("[[KEEP_1]] [[KEEP_2]] [[KEEP_2]]", &kept).is_err());
+    }
+
+    #[test]
+    fn test_align_headings_requires_same_structure() {
+        let translated =
+            "# Drucker offline\n\n## Problem\nA\n\n## Lösung\nB\n\n## Erwartetes Ergebnis\nC\n";
+        let aligned = align_headings(translated, SOURCE).unwrap();
+        assert!(aligned.starts_with("# Drucker offline\n"));
+        assert!(aligned.contains("## Solution\nB"));
+        assert!(aligned.contains("## Expected Result\nC"));
+
+        assert!(align_headings("# Drucker\n\n## Problem\nA\n", SOURCE).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_translate_keeps_code_and_fills_fields() {
+        let provider = FixedProvider::new(
+            4096,
+            "# Drucker offline\n\n## Problem\nDer Drucker unter [[KEEP_1]] ist offline.\n\n## Lösung\n1. [[KEEP_2]] ausführen und [[KEEP_3]] prüfen\n2. Den Spooler neu starten:\n\n[[KEEP_4]]\n\n## Erwartetes Ergebnis\nDer Drucker ist online.",
+        );
+
+        let (draft, provenance) = translate(
+            SOURCE,
+            &["printing".to_string()],
+            "DE",
+            &BTreeMap::new(),
+            &provider,
+            &mut |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(draft.language.as_deref(), Some("de"));
+        assert_eq!(draft.title, "Drucker offline");
+        assert!(draft
+            .solution
+            .contains("`lpstat -p` ausführen und /etc/cups/printers.conf prüfen"));
+        assert!(draft.solution.contains("sudo systemctl restart cups"));
+        assert_eq!(
+            draft.expected_result.as_deref(),
+            Some("Der Drucker ist online.")
+        );
+        assert!(draft.content_markdown.contains("## Lösung"));
+        assert_eq!(draft.tags, vec!["printing"]);
+
+        let prompt = &provider.prompts.lock().unwrap()[0];
+        assert!(prompt.contains("into German"));
+        assert_eq!(&provenance.user_prompt, prompt);
+        assert!(provenance.raw_output.contains("[[KEEP_4]]"));
+        assert!(!prompt.contains("systemctl"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_rejects_unknown_language_and_changed_sections() {
+        let provider = FixedProvider::new(
+            4096,
+            "# Drucker offline\n\n## Alles\n[[KEEP_1]] [[KEEP_2]] [[KEEP_3]] [[KEEP_4]]",
+        );
+
+        for language in ["xx", "de"] {
+            let result = translate(
+                SOURCE,
+                &[],
+                language,
+                &BTreeMap::new(),
+                &provider,
+                &mut |_| {},
+            )
+            .await;
+            assert!(result.is_err(), "{} accepted", language);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArticleReference } from "./ArticleReference";
+import type { FlaggedSection } from "./FlaggedSection";
+import type { Redaction } from "./Redaction";
+
+/**
+ * A drafted article split into the fields of `NewArticle`
+ */
+export type ArticleDraft = { title: string, problem: string, solution: string, expected_result?: string, prerequisites?: string, additional_notes?: string, tags: Array<string>, content_markdown: string, 
+/**
+ * True when the model answered with structured output; false when the
+ * fields were parsed back out of markdown
+ */
+structured: boolean, 
+/**
+ * Commands, paths, versions and similar details in the draft that do
+ * not appear in any source ticket
+ */
+ungrounded: Array<FlaggedSection>, 
+/**
+ * Id of the stored `DraftProvenance`; pass it back in `NewArticle` when
+ * saving so the record gets linked to the article
+ */
+provenance_id?: bigint, 
+/**
+ * Existing articles given to the model as reference material
+ */
+references: Array<ArticleReference>, 
+/**
+ * Sensitive values in the tickets that the model only saw as
+ * placeholders; `sensitive_data::restore` puts them back
+ */
+redactions: Array<Redaction>, 
+/**
+ * Problems with the source tickets to review before publishing, such as
+ * text that tries to give the model instructions
+ */
+warnings: Array<string>, 
+/**
+ * ISO 639-1 code when the draft was asked for in another language than
+ * the default
+ */
+language?: string, };
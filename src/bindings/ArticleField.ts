@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The `NewArticle` field an article section is stored in
+ */
+export type ArticleField = "problem" | "solution" | "expected_result" | "prerequisites" | "additional_notes";
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An existing KB article retrieved as reference material for a draft
+ */
+export type ArticleReference = { article_id: bigint, title: string, 
+/**
+ * Start of the article's problem statement
+ */
+summary: string, ticket_key?: string, confluence_url?: string, 
+/**
+ * Relevance to the drafted tickets; only comparable within one draft
+ */
+score: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How requests to Jira or Confluence are authenticated
+ */
+export type AuthMode = "auto" | "pat" | "basic";
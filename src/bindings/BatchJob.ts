@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FlaggedSection } from "./FlaggedSection";
+import type { JobStatus } from "./JobStatus";
+
+/**
+ * Drafting of one ticket of a batch. Also the payload of the
+ * `batch-job-status` event emitted on every status change.
+ */
+export type BatchJob = { id: bigint, batch_id: bigint, ticket_key: string, status: JobStatus, attempts: number, last_error?: string, 
+/**
+ * The saved draft, once the job is done
+ */
+article_id?: bigint, 
+/**
+ * When a failed attempt will be retried
+ */
+next_attempt_at?: string, 
+/**
+ * Problems found in the ticket while drafting it, as on `ArticleDraft`
+ */
+warnings: Array<string>, 
+/**
+ * Details of the draft the ticket does not back up, as on `ArticleDraft`
+ */
+ungrounded: Array<FlaggedSection>, updated_at: string, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BatchStatus } from "./BatchStatus";
+import type { LlmProviderKind } from "./LlmProviderKind";
+
+/**
+ * A JQL search being drafted in the background, one job per ticket
+ */
+export type BatchRun = { id: bigint, jql: string, template_id: string, 
+/**
+ * Jira instance the tickets are fetched from, kept so the batch can
+ * resume after a restart
+ */
+jira_url: string, provider: LlmProviderKind, llm_url: string, model: string, context_length?: number, 
+/**
+ * Tickets drafted at the same time
+ */
+concurrency: number, 
+/**
+ * Attempts per ticket before it is marked failed
+ */
+max_attempts: number, status: BatchStatus, total_jobs: number, completed_jobs: number, failed_jobs: number, created_at: string, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RenamedSection } from "./RenamedSection";
+
+/**
+ * How a draft's sections compare with its template's `output_structure`
+ */
+export type ConformanceReport = { 
+/**
+ * Template sections the draft does not have
+ */
+missing: Array<string>, 
+/**
+ * Draft sections that match no template section
+ */
+extra: Array<string>, 
+/**
+ * Template sections the draft has in a different place
+ */
+out_of_order: Array<string>, 
+/**
+ * Template sections the draft has under another name
+ */
+renamed: Array<RenamedSection>, };
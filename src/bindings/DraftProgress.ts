@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `draft-progress` event emitted while a draft is streaming
+ */
+export type DraftProgress = { draft_id: string, delta: string, chars_generated: number, };
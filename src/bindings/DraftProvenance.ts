@@ -0,0 +1,34 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArticleReference } from "./ArticleReference";
+import type { GenerationSettings } from "./GenerationSettings";
+import type { Redaction } from "./Redaction";
+
+/**
+ * What went into and came out of one draft generation, so reviewers can
+ * trace an article back to the prompt that produced it
+ */
+export type DraftProvenance = { id: bigint, 
+/**
+ * Unset until the draft is saved as an article
+ */
+article_id?: bigint, ticket_keys: Array<string>, template_id?: string, provider: string, model: string, options: GenerationSettings, system_prompt: string, user_prompt: string, 
+/**
+ * Existing articles included in the prompt
+ */
+references: Array<ArticleReference>, 
+/**
+ * Placeholders used in the prompt and the values they stand for
+ */
+redactions: Array<Redaction>, 
+/**
+ * Problems found in the source tickets, as in `ArticleDraft::warnings`
+ */
+warnings: Array<string>, 
+/**
+ * Model output before `post_process`
+ */
+raw_output: string, structured: boolean, prompt_eval_count?: bigint, eval_count?: bigint, 
+/**
+ * Time from sending the prompt to the last token
+ */
+duration_ms: bigint, created_at: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArticleStatus } from "./ArticleStatus";
+
+/**
+ * An existing article similar to the tickets about to be drafted
+ */
+export type DuplicateMatch = { article_id: bigint, title: string, ticket_key?: string, status: ArticleStatus, confluence_url?: string, 
+/**
+ * Cosine similarity of the embeddings, between 0 and 1
+ */
+similarity: number, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Model parameters a template drafts with. Unset values keep the backend's
+ * defaults.
+ */
+export type GenerationSettings = { temperature?: number, top_p?: number, 
+/**
+ * Context window in tokens; takes precedence over the configured one
+ */
+num_ctx?: number, 
+/**
+ * Fixed seed, for reproducible output
+ */
+seed?: bigint, 
+/**
+ * Maximum number of tokens to generate
+ */
+num_predict?: number, 
+/**
+ * Sequences that end generation when produced
+ */
+stop: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type JiraIssueLink = { key: string, 
+/**
+ * How this ticket relates to the linked one, e.g. "is duplicated by"
+ */
+relationship: string, summary: string, };
@@ -1,4 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { JiraComment } from "./JiraComment";
+import type { JiraIssueLink } from "./JiraIssueLink";
+import type { TimelineEvent } from "./TimelineEvent";
 
-export type JiraTicket = { key: string, summary: string, description?: string, status: string, priority?: string, resolution?: string, labels: Array<string>, components: Array<string>, comments: Array<JiraComment>, created: string, updated: string, };
+export type JiraTicket = { key: string, summary: string, description?: string, status: string, priority?: string, resolution?: string, labels: Array<string>, components: Array<string>, comments: Array<JiraComment>, 
+/**
+ * Issues linked from this ticket (duplicates, relates to, ...)
+ */
+linked_issues: Array<JiraIssueLink>, 
+/**
+ * Creation, status, assignee, priority and resolution changes and
+ * comments, oldest first. Changes are only known for tickets fetched
+ * one by one, not for search results.
+ */
+timeline: Array<TimelineEvent>, created: string, updated: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A language articles can be drafted or translated into
+ */
+export type Language = { 
+/**
+ * ISO 639-1 code, e.g. "de"
+ */
+code: string, 
+/**
+ * English name, as given to the model
+ */
+name: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which API the configured LLM server speaks
+ */
+export type LlmProviderKind = "ollama" | "openai_compatible";
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `model-pull-progress` event emitted while a model downloads
+ */
+export type ModelPullProgress = { model: string, 
+/**
+ * Ollama's status line, e.g. "pulling manifest" or "success"
+ */
+status: string, 
+/**
+ * Bytes downloaded of the current layer
+ */
+completed?: bigint, 
+/**
+ * Size of the current layer in bytes
+ */
+total?: bigint, };
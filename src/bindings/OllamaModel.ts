@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A model installed on the Ollama server, as listed by `/api/tags`
+ */
+export type OllamaModel = { name: string, 
+/**
+ * Size on disk in bytes
+ */
+size: bigint, modified_at: string, parameter_size?: string, quantization_level?: string, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Details of one model, as reported by `/api/show`
+ */
+export type OllamaModelDetails = { name: string, family?: string, parameter_size?: string, quantization_level?: string, 
+/**
+ * Longest context the model was trained for. Useful as an upper bound
+ * for the configured context length.
+ */
+context_length?: bigint, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A sensitive value replaced by a placeholder before ticket content is sent
+ * to the LLM. Kept locally so the real value can be put back in the draft.
+ */
+export type Redaction = { 
+/**
+ * What the model saw instead, e.g. `<IP_1>`
+ */
+placeholder: string, pattern_type: string, value: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One turn of the refinement conversation kept for an article
+ */
+export type RefinementMessage = { id: bigint, article_id: bigint, role: string, content: string, created_at: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TagSource } from "./TagSource";
+
+/**
+ * A label proposed for an article, best first
+ */
+export type TagSuggestion = { 
+/**
+ * Already normalized to Confluence label syntax
+ */
+tag: string, score: number, 
+/**
+ * Where the tag came from, in the order it was found
+ */
+sources: Array<TagSource>, };
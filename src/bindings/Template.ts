@@ -1,3 +1,18 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArticleField } from "./ArticleField";
+import type { GenerationSettings } from "./GenerationSettings";
 
-export type Template = { id: string, name: string, slug: string, description: string, system_prompt: string, output_structure: string, is_builtin: boolean, created_at: string, };
+export type Template = { id: string, name: string, slug: string, description: string, system_prompt: string, output_structure: string, 
+/**
+ * Which article field each `output_structure` heading fills. Headings
+ * not listed fall back to matching common names like "Resolution".
+ */
+field_mapping: { [key in string]?: ArticleField }, 
+/**
+ * Sampling settings used when drafting with this template
+ */
+generation: GenerationSettings, 
+/**
+ * Model to draft with instead of the one configured in the settings
+ */
+model?: string, is_builtin: boolean, created_at: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How well a template suits a ticket, as suggested by `recommend_templates`
+ */
+export type TemplateRecommendation = { template_id: string, template_name: string, 
+/**
+ * Between 0 and 1; the confidences of one recommendation add up to 1
+ */
+confidence: number, reason: string, };
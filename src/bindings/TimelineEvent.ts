@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TimelineEventKind } from "./TimelineEventKind";
+
+/**
+ * One entry of a ticket's history
+ */
+export type TimelineEvent = { 
+/**
+ * As reported by Jira, e.g. "2024-01-01T10:00:00.000+0000"
+ */
+timestamp: string, author: string, kind: TimelineEventKind, 
+/**
+ * Value before the change
+ */
+from?: string, 
+/**
+ * Value after the change, or the text of a comment
+ */
+to?: string, };
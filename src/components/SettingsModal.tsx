@@ -2,6 +2,7 @@ import { useState, useEffect } from 'react';
 import { invoke } from '../lib/tauri';
 import { useAuthStore } from '../stores/authStore';
 import { useSettingsStore } from '../stores/settingsStore';
+import type { AuthMode } from '../bindings/AuthMode';
 
 interface Props {
   isOpen: boolean;
@@ -11,11 +12,13 @@ interface Props {
 export function SettingsModal({ isOpen, onClose }: Props) {
   const [jiraUrl, setJiraUrl] = useState('');
   const [jiraPat, setJiraPat] = useState('');
+  const [jiraEmail, setJiraEmail] = useState('');
   const [testingJira, setTestingJira] = useState(false);
   const [jiraTestResult, setJiraTestResult] = useState<'success' | 'error' | null>(null);
 
   const [confluenceUrl, setConfluenceUrl] = useState('');
   const [confluencePat, setConfluencePat] = useState('');
+  const [confluenceEmail, setConfluenceEmail] = useState('');
   const [testingConfluence, setTestingConfluence] = useState(false);
   const [confluenceTestResult, setConfluenceTestResult] = useState<'success' | 'error' | null>(null);
 
@@ -46,17 +49,21 @@ export function SettingsModal({ isOpen, onClose }: Props) {
     setJiraTestResult(null);
 
     try {
-      const result = await invoke<boolean>('test_jira_connection', {
+      // Resolves to the auth mode Jira accepted (PAT or Cloud email + API token)
+      const authMode = await invoke<AuthMode>('test_jira_connection', {
         baseUrl: jiraUrl,
         pat: jiraPat,
+        email: jiraEmail || null,
       });
 
-      if (result) {
+      if (authMode) {
         setJiraTestResult('success');
         // Save configuration
         await invoke('save_jira_config', {
           baseUrl: jiraUrl,
           pat: jiraPat,
+          email: jiraEmail || null,
+          authMode,
         });
         setJiraConnected(true, jiraUrl);
         setJiraPat(''); // Clear PAT from state for security
@@ -91,17 +98,20 @@ export function SettingsModal({ isOpen, onClose }: Props) {
     setConfluenceTestResult(null);
 
     try {
-      const result = await invoke<boolean>('test_confluence_connection', {
+      const authMode = await invoke<AuthMode>('test_confluence_connection', {
         baseUrl: confluenceUrl,
         pat: confluencePat,
+        email: confluenceEmail || null,
       });
 
-      if (result) {
+      if (authMode) {
         setConfluenceTestResult('success');
         // Save configuration
         await invoke('save_confluence_config', {
           baseUrl: confluenceUrl,
           pat: confluencePat,
+          email: confluenceEmail || null,
+          authMode,
         });
         // Persist URL to settings store
         persistConfluenceUrl(confluenceUrl);
@@ -171,6 +181,23 @@ export function SettingsModal({ isOpen, onClose }: Props) {
                   />
                 </div>
 
+                <div>
+                  <label htmlFor="jiraEmail" className="block text-sm font-medium mb-1">
+                    Account Email (Cloud only)
+                  </label>
+                  <input
+                    id="jiraEmail"
+                    type="email"
+                    value={jiraEmail}
+                    onChange={(e) => setJiraEmail(e.target.value)}
+                    placeholder="you@example.com"
+                    className="w-full px-3 py-2 border border-gray-300 rounded-md"
+                  />
+                  <p className="text-xs text-gray-500 mt-1">
+                    For Atlassian Cloud, enter your email and an API token below
+                  </p>
+                </div>
+
                 <div>
                   <label htmlFor="jiraPat" className="block text-sm font-medium mb-1">
                     Personal Access Token
@@ -245,6 +272,23 @@ export function SettingsModal({ isOpen, onClose }: Props) {
                   />
                 </div>
 
+                <div>
+                  <label htmlFor="confluenceEmail" className="block text-sm font-medium mb-1">
+                    Account Email (Cloud only)
+                  </label>
+                  <input
+                    id="confluenceEmail"
+                    type="email"
+                    value={confluenceEmail}
+                    onChange={(e) => setConfluenceEmail(e.target.value)}
+                    placeholder="you@example.com"
+                    className="w-full px-3 py-2 border border-gray-300 rounded-md"
+                  />
+                  <p className="text-xs text-gray-500 mt-1">
+                    For Atlassian Cloud, enter your email and an API token below
+                  </p>
+                </div>
+
                 <div>
                   <label htmlFor="confluencePat" className="block text-sm font-medium mb-1">
                     Personal Access Token